@@ -299,7 +299,10 @@ _NORMAL_DEPENDENCIES = {
             "cargo_toml": "@crates_vendor__cargo_toml-0.16.3//:cargo_toml",
             "clap": "@crates_vendor__clap-3.2.20//:clap",
             "lazy_static": "@crates_vendor__lazy_static-1.4.0//:lazy_static",
+            "proc-macro2": "@crates_vendor__proc-macro2-1.0.69//:proc_macro2",
+            "serde": "@crates_vendor__serde-1.0.156//:serde",
             "syn": "@crates_vendor__syn-1.0.109//:syn",
+            "toml": "@crates_vendor__toml-0.8.2//:toml",
         },
     },
 }
@@ -0,0 +1,2 @@
+#[path = "redirect/first.rs"]
+mod first;
@@ -0,0 +1,2 @@
+#[path = "deeper/second.rs"]
+mod second;
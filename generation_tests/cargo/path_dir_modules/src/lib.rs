@@ -0,0 +1,5 @@
+#[path = "platform"]
+mod sys;
+
+#[path = "backend"]
+mod imp;
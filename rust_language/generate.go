@@ -426,7 +426,22 @@ func (l *rustLang) discoverModule(c *config.Config, file string, args *language.
 			var externModPath string
 			var childIsModRoot bool
 
-			if isModRoot {
+			if modPath, ok := response.ExternModPaths[externMod]; ok && filepath.Ext(modPath) != ".rs" {
+				// a directory-style #[path = "platform"] is either platform.rs with its children
+				// under platform/, or platform/mod.rs
+				externModPath = filepath.Join(dirname, modPath+".rs")
+				childIsModRoot = false
+
+				if !fileExists(externModPath, args) {
+					externModPath = filepath.Join(dirname, modPath, "mod.rs")
+					childIsModRoot = true
+				}
+			} else if ok {
+				// #[path = "..."] is relative to the directory of the current file, and the file it
+				// points to owns its directory like a mod.rs file does
+				externModPath = filepath.Join(dirname, modPath)
+				childIsModRoot = true
+			} else if isModRoot {
 				// first check for an adjacent file
 				externModPath = filepath.Join(dirname, externMod+".rs")
 				childIsModRoot = false
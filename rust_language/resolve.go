@@ -81,12 +81,16 @@ func (l *rustLang) Resolve(c *config.Config, ix *resolve.RuleIndex,
 				if ruleData.testedCrate == nil {
 					// this is a standalone test
 					imports = append(response.GetImports(), response.GetTestImports()...)
+					imports = append(imports, response.GetCoverageImports()...)
+					imports = append(imports, response.GetMiriImports()...)
 				} else {
 					// this is a test associated with another target; don't duplicate the deps
 					imports = response.GetTestImports()
 				}
 			} else {
-				imports = response.GetImports()
+				// coverage and miri builds compile the same targets, so their deps have to be available
+				imports = append(response.GetImports(), response.GetCoverageImports()...)
+				imports = append(imports, response.GetMiriImports()...)
 			}
 
 			for _, imp := range imports {
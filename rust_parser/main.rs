@@ -1,38 +1,562 @@
 #![deny(unused_must_use)]
 
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::error::Error;
-use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
 
 use protobuf::{CodedInputStream, CodedOutputStream, RepeatedField};
 
 use messages_rust_proto::{
-    CargoCrateInfo, CargoTomlRequest, CargoTomlResponse, LockfileCratesRequest,
-    LockfileCratesRequest_oneof_lockfile, LockfileCratesResponse, Request, Request_oneof_kind,
-    RustImportsRequest, RustImportsResponse,
+    CargoCrateInfo, CargoTomlRequest, CargoTomlResponse, Diagnostic, Hints, Import, ImportKind,
+    ImportList, ImportSource, LockfileCratesRequest, LockfileCratesRequest_oneof_lockfile,
+    LockfileCratesResponse, Request, Request_oneof_kind, RustImportsRequest, RustImportsResponse,
 };
 
 #[derive(clap::Parser)]
 enum Args {
-    OneShot { path: PathBuf },
-    StreamProto,
+    OneShot {
+        path: PathBuf,
+        #[clap(flatten)]
+        parse_args: ParseArgs,
+        /// Only check that the file parses, without extracting anything
+        #[clap(long)]
+        parse_only: bool,
+        #[clap(long, value_enum, default_value = "text")]
+        format: Format,
+        /// Compare the imports against --deps instead of printing them, and exit with an error if
+        /// any are missing or unused. Deps are only unused if none of the given file and --srcs
+        /// use them, so pass the other files of the crate with --srcs
+        #[clap(long)]
+        check: bool,
+        /// Crate names of the declared deps to compare against with --check, e.g. `serde,tokio`
+        #[clap(long, value_delimiter = ',', requires = "check")]
+        deps: Vec<String>,
+        /// The other source files of the crate for --check, e.g. `src/a.rs,src/b.rs`
+        #[clap(long, value_delimiter = ',', requires = "check")]
+        srcs: Vec<PathBuf>,
+        /// Print warnings to stderr as one JSON object per line, with `severity`, `code` and
+        /// `message` keys, and `line` and `column` if they have a location, instead of as text. In
+        /// stream-proto mode they are always in the response's diagnostics
+        #[clap(long)]
+        warnings_as_json: bool,
+        #[clap(flatten)]
+        verbosity: VerbosityArgs,
+    },
+    /// Print the version of syn the parser was built with, since it decides which syntax parses
+    ReportSynVersion,
+    StreamProto {
+        #[clap(flatten)]
+        parse_args: ParseArgs,
+        /// Only include these fields in rust imports responses, e.g. `imports,has_main`
+        #[clap(long, value_delimiter = ',')]
+        only: Vec<String>,
+        /// Leave these fields out of rust imports responses, e.g. `test_imports`
+        #[clap(long, value_delimiter = ',')]
+        skip: Vec<String>,
+        /// Cap the number of imports in each bucket per file; responses that hit the cap are
+        /// marked as truncated
+        #[clap(long, default_value_t = 10000)]
+        max_imports: usize,
+        /// Shape of the imports in rust imports responses
+        #[clap(long, value_enum, default_value = "proto-v1")]
+        format: ProtoFormat,
+        #[clap(flatten)]
+        verbosity: VerbosityArgs,
+    },
+}
+
+/// How much is written to stderr. Levels are 0 (errors only), 1 (also warnings, the default), 2
+/// (also each file as it's parsed) and 3 (also the bucket each import is attributed to).
+#[derive(clap::Args)]
+struct VerbosityArgs {
+    /// Print more to stderr; can be repeated: -v prints each file as it's parsed, -vv also traces
+    /// the bucket each import is attributed to
+    #[clap(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+    /// Only print errors to stderr
+    #[clap(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+impl VerbosityArgs {
+    fn level(&self) -> u8 {
+        if self.quiet {
+            0
+        } else {
+            1 + self.verbose
+        }
+    }
+}
+
+/// Options that are passed through to the parser.
+#[derive(clap::Args)]
+struct ParseArgs {
+    /// Attribute that marks a top-level function as the entry point even if it isn't named main,
+    /// e.g. `rocket::launch`; can be repeated
+    #[clap(long = "entry-attr")]
+    entry_attrs: Vec<String>,
+    /// Include a hash of each file's content, so that unchanged files can be skipped next time
+    #[clap(long)]
+    emit_hash: bool,
+    /// Crates that are built from this workspace, e.g. `core_lib,utils`; if set, imports are also
+    /// split into workspace_imports and external_imports
+    #[clap(long, value_delimiter = ',')]
+    workspace_crates: Vec<String>,
+    /// Include the names of #[test] functions, along with whether they are should_panic, ignore
+    /// or async
+    #[clap(long)]
+    emit_test_names: bool,
+    /// Lowercase the first letter of uppercase candidates instead of dropping them, to keep crates
+    /// that are only referred to by an alias like `extern crate Foo`. This also reports type names
+    /// like `String` as crates, so the results need to be filtered against the known crates
+    #[clap(long)]
+    normalize_case: bool,
+    /// Include the paths of the mods that contain #[test] or #[bench] functions
+    #[clap(long)]
+    group_test_by_mod: bool,
+    /// Order of the imports in each bucket [default: sorted]
+    #[clap(long, value_enum)]
+    order: Option<Order>,
+    /// Report all imports of files with a main function as test imports, for test binaries with a
+    /// custom harness. has_main is still set
+    #[clap(long)]
+    treat_bin_as_test: bool,
+    /// Include every feature that a cfg in the file refers to, whether or not it gates any imports
+    #[clap(long)]
+    list_features: bool,
+    /// Name of the crate the file belongs to, which is left out of the imports
+    #[clap(long)]
+    crate_name: Option<String>,
+    /// Infer --crate-name from the path if it isn't set: `my-crate/src/**/*.rs` belongs to
+    /// `my_crate`. Nothing is left out for binaries (`src/main.rs` and `src/bin/`) or paths outside
+    /// of a `src` directory
+    #[clap(long)]
+    crate_name_from_path: bool,
+    /// Skip scanning macro_rules! and asm! bodies with more than this many tokens for imports.
+    /// Skipped macros are listed in macros_skipped
+    #[clap(long)]
+    max_macro_tokens: Option<usize>,
+    /// Ignore test-only code completely, including #[test] functions and #[cfg(test)] mods, so no
+    /// test_imports are reported. Test-only mods and functions aren't visited at all
+    #[clap(long)]
+    prod_only: bool,
+    /// Include the public functions, structs, enums and traits with their mod paths, e.g. for
+    /// diffing the API across commits
+    #[clap(long)]
+    emit_public_api: bool,
+    /// Scan the bodies of quote!, quote_spanned! and parse_quote! for imports. They're skipped by
+    /// default, since in a proc macro they describe code that is compiled in the caller's crate
+    #[clap(long)]
+    scan_quote_bodies: bool,
+    /// Include the tree of cfg scopes as JSON, each with its predicate, the bucket and feature its
+    /// imports are attributed to, the imports, and its children; for auditing how imports are
+    /// bucketed. In one-shot mode it's printed instead of the imports
+    #[clap(long)]
+    emit_cfg_tree: bool,
+    /// TOML file with defaults for the options above, using the field names of
+    /// parser::ParseConfig, e.g. `entry_attrs = ["rocket::launch"]` or `source_order = true`.
+    /// Flags that are given override the file
+    #[clap(long)]
+    config_file: Option<PathBuf>,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum Order {
+    /// Alphabetical
+    Sorted,
+    /// In the order they first appear in the file
+    Source,
+}
+
+impl TryFrom<ParseArgs> for parser::ParseConfig {
+    type Error = Box<dyn Error>;
+
+    fn try_from(args: ParseArgs) -> Result<Self, Self::Error> {
+        let mut config: Self = match &args.config_file {
+            Some(path) => {
+                let content = std::fs::read_to_string(path)
+                    .map_err(|err| format!("{}: {}", path.display(), err))?;
+                toml::from_str(&content).map_err(|err| format!("{}: {}", path.display(), err))?
+            }
+            None => Self::default(),
+        };
+
+        // flags can only turn options on, or replace lists, so anything not given is left as is
+        if !args.entry_attrs.is_empty() {
+            config.entry_attrs = args.entry_attrs;
+        }
+        config.emit_hash |= args.emit_hash;
+        if !args.workspace_crates.is_empty() {
+            config.workspace_crates = args.workspace_crates;
+        }
+        config.emit_test_names |= args.emit_test_names;
+        config.normalize_case |= args.normalize_case;
+        config.group_test_by_mod |= args.group_test_by_mod;
+        if let Some(order) = args.order {
+            config.source_order = order == Order::Source;
+        }
+        config.treat_bin_as_test |= args.treat_bin_as_test;
+        config.list_features |= args.list_features;
+        if args.crate_name.is_some() {
+            config.crate_name = args.crate_name;
+        }
+        config.crate_name_from_path |= args.crate_name_from_path;
+        if args.max_macro_tokens.is_some() {
+            config.max_macro_tokens = args.max_macro_tokens;
+        }
+        config.prod_only |= args.prod_only;
+        config.emit_public_api |= args.emit_public_api;
+        config.emit_cfg_tree |= args.emit_cfg_tree;
+        config.scan_quote_bodies |= args.scan_quote_bodies;
+
+        Ok(config)
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum ProtoFormat {
+    /// One list of imports per bucket, and one map per kind of feature gate
+    ProtoV1,
+    /// A single all_imports list where each import has a kind and a condition
+    ProtoV2,
+}
+
+#[derive(clap::ValueEnum, Clone)]
+enum Format {
+    /// Human-readable list of imports
+    Text,
+    /// [dependencies] and [dev-dependencies] tables for bootstrapping a Cargo.toml
+    CargoToml,
+    /// One sorted `<bucket> <crate>` line per import in each bucket, stable enough to diff;
+    /// feature buckets are named like `feature:<name>` and `anti_feature:<name>`
+    ImportsPerLine,
+    /// The boolean hints packed into a single hex number; see print_hints_bitfield for the bits
+    HintsBitfield,
+}
+
+/// Fields of RustImportsResponse that can be selected with --only and --skip. Hints can be selected
+/// individually.
+const RESPONSE_FIELDS: &[&str] = &[
+    "imports",
+    "test_imports",
+    "coverage_imports",
+    "miri_imports",
+    "feature_imports",
+    "anti_feature_imports",
+    "referenced_features",
+    "std_externs",
+    "macros_skipped",
+    "diagnostics",
+    "public_api",
+    "native_links",
+    "cfg_tree",
+    "extern_mods",
+    "extern_mod_paths",
+    "keep_imports",
+    "reexports",
+    "content_hash",
+    "workspace_imports",
+    "external_imports",
+    "test_fns",
+    "import_sources",
+    "test_mods",
+    "has_main",
+    "has_test",
+    "has_proc_macro",
+    "has_repr_c",
+    "has_macro_generated_items",
+    "recursion_limit",
+    "type_length_limit",
+    "module_count",
+    "max_module_depth",
+    "crate_lint_levels",
+    "has_coverage_off",
+    "has_unresolvable_derive",
+    "is_no_std",
+    "public_api_markers",
+];
+
+/// Prunes rust imports responses down to the fields that the caller asked for, to avoid serializing
+/// data that is going to be thrown away.
+struct ResponseFilter {
+    only: Vec<String>,
+    skip: Vec<String>,
+}
+
+impl ResponseFilter {
+    fn new(only: Vec<String>, skip: Vec<String>) -> Result<Self, Box<dyn Error>> {
+        for field in only.iter().chain(skip.iter()) {
+            if !RESPONSE_FIELDS.contains(&field.as_str()) {
+                return Err(format!(
+                    "unknown field {}, expected one of: {}",
+                    field,
+                    RESPONSE_FIELDS.join(", ")
+                )
+                .into());
+            }
+        }
+        Ok(Self { only, skip })
+    }
+
+    fn includes(&self, field: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|f| f == field))
+            && !self.skip.iter().any(|f| f == field)
+    }
+
+    fn apply(&self, response: &mut RustImportsResponse) {
+        if !self.includes("imports") {
+            response.clear_imports();
+        }
+        if !self.includes("test_imports") {
+            response.clear_test_imports();
+        }
+        if !self.includes("coverage_imports") {
+            response.clear_coverage_imports();
+        }
+        if !self.includes("miri_imports") {
+            response.clear_miri_imports();
+        }
+        if !self.includes("feature_imports") {
+            response.clear_feature_imports();
+        }
+        if !self.includes("anti_feature_imports") {
+            response.clear_anti_feature_imports();
+        }
+        if !self.includes("referenced_features") {
+            response.clear_referenced_features();
+        }
+        if !self.includes("std_externs") {
+            response.clear_std_externs();
+        }
+        if !self.includes("macros_skipped") {
+            response.clear_macros_skipped();
+        }
+        if !self.includes("diagnostics") {
+            response.clear_diagnostics();
+        }
+        if !self.includes("public_api") {
+            response.clear_public_api();
+        }
+        if !self.includes("native_links") {
+            response.clear_native_links();
+        }
+        if !self.includes("cfg_tree") {
+            response.clear_cfg_tree();
+        }
+        if !self.includes("extern_mods") {
+            response.clear_extern_mods();
+        }
+        if !self.includes("extern_mod_paths") {
+            response.clear_extern_mod_paths();
+        }
+        if !self.includes("keep_imports") {
+            response.clear_keep_imports();
+        }
+        if !self.includes("reexports") {
+            response.clear_reexports();
+        }
+        if !self.includes("content_hash") {
+            response.clear_content_hash();
+        }
+        if !self.includes("workspace_imports") {
+            response.clear_workspace_imports();
+        }
+        if !self.includes("external_imports") {
+            response.clear_external_imports();
+        }
+        if !self.includes("test_fns") {
+            response.clear_test_fns();
+        }
+        if !self.includes("import_sources") {
+            response.clear_import_sources();
+        }
+        if !self.includes("test_mods") {
+            response.clear_test_mods();
+        }
+
+        let hints = response.mut_hints();
+        if !self.includes("has_main") {
+            hints.has_main = false;
+        }
+        if !self.includes("has_test") {
+            hints.has_test = false;
+        }
+        if !self.includes("has_proc_macro") {
+            hints.has_proc_macro = false;
+        }
+        if !self.includes("has_repr_c") {
+            hints.has_repr_c = false;
+        }
+        if !self.includes("has_macro_generated_items") {
+            hints.has_macro_generated_items = false;
+        }
+        if !self.includes("recursion_limit") {
+            hints.recursion_limit = 0;
+        }
+        if !self.includes("type_length_limit") {
+            hints.type_length_limit = 0;
+        }
+        if !self.includes("module_count") {
+            hints.module_count = 0;
+        }
+        if !self.includes("max_module_depth") {
+            hints.max_module_depth = 0;
+        }
+        if !self.includes("crate_lint_levels") {
+            hints.clear_crate_lint_levels();
+        }
+        if !self.includes("has_coverage_off") {
+            hints.has_coverage_off = false;
+        }
+        if !self.includes("has_unresolvable_derive") {
+            hints.has_unresolvable_derive = false;
+        }
+        if !self.includes("is_no_std") {
+            hints.is_no_std = false;
+        }
+        if !self.includes("public_api_markers") {
+            hints.clear_public_api_markers();
+        }
+    }
+}
+
+/// Options that apply to every rust imports request in stream-proto mode.
+struct RustImportsOptions {
+    config: parser::ParseConfig,
+    filter: ResponseFilter,
+    max_imports: usize,
+    format: ProtoFormat,
+    verbosity: u8,
+}
+
+/// Caps the imports at max_imports, keeping the first ones. Imports are always ordered, so the
+/// result is deterministic. Returns whether any imports were dropped.
+fn truncate_imports(imports: &mut Vec<String>, max_imports: usize) -> bool {
+    if imports.len() <= max_imports {
+        return false;
+    }
+    imports.truncate(max_imports);
+    true
+}
+
+/// Caps every bucket at max_imports, and drops the crates that are no longer in any bucket from
+/// the lists that describe them, like import_sources. Returns whether any imports were dropped.
+fn truncate_all_imports(rust_imports: &mut parser::RustImports, max_imports: usize) -> bool {
+    let mut truncated = false;
+    for imports in [
+        &mut rust_imports.imports,
+        &mut rust_imports.test_imports,
+        &mut rust_imports.coverage_imports,
+        &mut rust_imports.miri_imports,
+    ] {
+        truncated |= truncate_imports(imports, max_imports);
+    }
+    if !truncated {
+        return false;
+    }
+
+    let kept: HashSet<String> = rust_imports.all_imports().cloned().collect();
+    for imports in [
+        &mut rust_imports.keep_imports,
+        &mut rust_imports.reexports,
+        &mut rust_imports.workspace_imports,
+        &mut rust_imports.external_imports,
+    ]
+    .into_iter()
+    .chain(rust_imports.feature_imports.values_mut())
+    .chain(rust_imports.anti_feature_imports.values_mut())
+    {
+        imports.retain(|import| kept.contains(import));
+        truncate_imports(imports, max_imports);
+    }
+    rust_imports
+        .feature_imports
+        .retain(|_, imports| !imports.is_empty());
+    rust_imports
+        .anti_feature_imports
+        .retain(|_, imports| !imports.is_empty());
+    rust_imports
+        .import_sources
+        .retain(|import_source| kept.contains(import_source.get_name()));
+    rust_imports
+        .import_conditions
+        .retain(|import, _| kept.contains(import));
+    true
+}
+
+fn to_import_lists(imports: HashMap<String, Vec<String>>) -> HashMap<String, ImportList> {
+    imports
+        .into_iter()
+        .map(|(key, imports)| {
+            let mut import_list = ImportList::default();
+            import_list.set_imports(RepeatedField::from_vec(imports));
+            (key, import_list)
+        })
+        .collect()
 }
 
 fn handle_rust_imports_request(
     request: RustImportsRequest,
+    options: &RustImportsOptions,
 ) -> Result<RustImportsResponse, Box<dyn Error>> {
-    let rust_imports = parser::parse_imports(PathBuf::from(request.file_path));
+    if options.verbosity >= 2 {
+        eprintln!("parsing {}", request.file_path);
+    }
+    let rust_imports = parser::parse_imports(PathBuf::from(request.file_path), &options.config);
 
     let mut response = RustImportsResponse::default();
     match rust_imports {
-        Ok(rust_imports) => {
+        Ok(mut rust_imports) => {
+            if options.verbosity >= 3 {
+                print_import_trace(&mut io::stderr().lock(), &rust_imports)?;
+            }
+
+            // protect against pathological (e.g. generated or fuzzed) files
+            let truncated = truncate_all_imports(&mut rust_imports, options.max_imports);
+
             response.set_success(true);
+            response.set_truncated(truncated);
             response.set_hints(rust_imports.hints);
             response.imports = RepeatedField::from_vec(rust_imports.imports);
             response.test_imports = RepeatedField::from_vec(rust_imports.test_imports);
+            response.coverage_imports = RepeatedField::from_vec(rust_imports.coverage_imports);
+            response.miri_imports = RepeatedField::from_vec(rust_imports.miri_imports);
+            response.feature_imports = to_import_lists(rust_imports.feature_imports);
+            response.anti_feature_imports = to_import_lists(rust_imports.anti_feature_imports);
+            response.referenced_features =
+                RepeatedField::from_vec(rust_imports.referenced_features);
+            response.std_externs = RepeatedField::from_vec(rust_imports.std_externs);
+            response.macros_skipped = RepeatedField::from_vec(rust_imports.macros_skipped);
+            response.diagnostics = RepeatedField::from_vec(rust_imports.diagnostics);
+            response.public_api = RepeatedField::from_vec(rust_imports.public_api);
+            response.native_links = RepeatedField::from_vec(rust_imports.native_links);
+            if let Some(cfg_tree) = &rust_imports.cfg_tree {
+                response.set_cfg_tree(cfg_tree_json(cfg_tree));
+            }
             response.extern_mods = RepeatedField::from_vec(rust_imports.extern_mods);
+            response.extern_mod_paths = rust_imports.extern_mod_paths;
+            response.keep_imports = RepeatedField::from_vec(rust_imports.keep_imports);
+            response.reexports = RepeatedField::from_vec(rust_imports.reexports);
+            response.workspace_imports = RepeatedField::from_vec(rust_imports.workspace_imports);
+            response.external_imports = RepeatedField::from_vec(rust_imports.external_imports);
+            response.test_fns = RepeatedField::from_vec(rust_imports.test_fns);
+            response.import_sources = RepeatedField::from_vec(rust_imports.import_sources);
+            response.test_mods = RepeatedField::from_vec(rust_imports.test_mods);
+            if let Some(content_hash) = rust_imports.content_hash {
+                response.set_content_hash(content_hash);
+            }
+            let import_sources = response.get_import_sources().to_vec();
+            options.filter.apply(&mut response);
+            if options.format == ProtoFormat::ProtoV2 {
+                to_proto_v2(
+                    &mut response,
+                    &import_sources,
+                    &rust_imports.import_conditions,
+                );
+            }
         }
         Err(err) => {
             // Don't crash gazelle if we encounter an error, instead bubble it up so that we can
@@ -46,6 +570,44 @@ fn handle_rust_imports_request(
     Ok(response)
 }
 
+/// Moves the per-bucket lists of imports into all_imports, tagging each with how it's declared
+/// and the feature predicate it's needed under. The feature lists are folded into the conditions,
+/// so each import is listed once.
+fn to_proto_v2(
+    response: &mut RustImportsResponse,
+    import_sources: &[ImportSource],
+    import_conditions: &HashMap<String, String>,
+) {
+    let mut all_imports = Vec::new();
+    for (imports, kind) in [
+        (response.take_imports(), ImportKind::REGULAR),
+        (response.take_test_imports(), ImportKind::TEST),
+        (response.take_coverage_imports(), ImportKind::COVERAGE),
+        (response.take_miri_imports(), ImportKind::MIRI),
+    ] {
+        for name in imports.into_vec() {
+            let mut import = Import::default();
+            if let Some(import_source) = import_sources
+                .iter()
+                .find(|import_source| import_source.get_name() == name)
+            {
+                import.set_via_extern_crate(import_source.get_via_extern_crate());
+                import.set_via_use(import_source.get_via_use());
+            }
+            if let Some(condition) = import_conditions.get(&name) {
+                import.set_condition(condition.clone());
+            }
+            import.set_name(name);
+            import.set_kind(kind);
+            all_imports.push(import);
+        }
+    }
+    response.clear_feature_imports();
+    response.clear_anti_feature_imports();
+
+    response.all_imports = RepeatedField::from_vec(all_imports);
+}
+
 fn handle_lockfile_crates_request(
     request: LockfileCratesRequest,
 ) -> Result<LockfileCratesResponse, Box<dyn Error>> {
@@ -106,68 +668,929 @@ fn handle_cargo_toml_request(
     Ok(response)
 }
 
+/// Crates that are always available without being declared as deps. Keep in sync with Builtins in
+/// rust_language/builtins.go.
+const BUILTINS: &[&str] = &[
+    // standard library
+    "std",
+    "core",
+    "alloc",
+    "test",
+    // proc macros
+    "proc_macro",
+    // tools
+    "rustfmt",
+    "clippy",
+    // primitive types
+    "bool",
+    "char",
+    "str",
+    "f32",
+    "f64",
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "i128",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "isize",
+    "usize",
+];
+
+/// Reports the deps missing from and unused by all of the files of a crate, returning whether
+/// there were none.
+fn check_deps(
+    out: &mut impl Write,
+    rust_imports: &[parser::RustImports],
+    deps: &[String],
+) -> io::Result<bool> {
+    // NOTE: these are crate names, but accept package names with hyphens too
+    let deps: BTreeSet<String> = deps.iter().map(|dep| dep.replace('-', "_")).collect();
+    let imports: BTreeSet<&String> = rust_imports
+        .iter()
+        .flat_map(parser::RustImports::all_imports)
+        .filter(|import| !BUILTINS.contains(&import.as_str()))
+        .collect();
+
+    let missing: Vec<&String> = imports
+        .iter()
+        .copied()
+        .filter(|import| !deps.contains(*import))
+        .collect();
+    let unused: Vec<&String> = deps.iter().filter(|dep| !imports.contains(dep)).collect();
+
+    if !missing.is_empty() {
+        writeln!(out, "Missing deps:")?;
+        for dep in &missing {
+            writeln!(out, "  {}", dep)?;
+        }
+    }
+    if !unused.is_empty() {
+        writeln!(out, "Unused deps:")?;
+        for dep in &unused {
+            writeln!(out, "  {}", dep)?;
+        }
+    }
+
+    Ok(missing.is_empty() && unused.is_empty())
+}
+
+/// Checks the syntax of the file, printing the error as `<path>:<line>:<column>: <message>` if there
+/// is one. Returns whether the file parsed.
+fn report_syntax(out: &mut impl Write, path: &Path) -> io::Result<bool> {
+    match parser::check_syntax(path.to_path_buf()) {
+        Ok(()) => Ok(true),
+        Err(err) => {
+            writeln!(out, "{}:{}", path.display(), err)?;
+            Ok(false)
+        }
+    }
+}
+
+fn print_diagnostics(
+    out: &mut impl Write,
+    diagnostics: &[Diagnostic],
+    as_json: bool,
+) -> io::Result<()> {
+    for diagnostic in diagnostics {
+        let has_location = diagnostic.get_line() != 0;
+        if as_json {
+            let location = if has_location {
+                format!(
+                    r#","line":{},"column":{}"#,
+                    diagnostic.get_line(),
+                    diagnostic.get_column()
+                )
+            } else {
+                String::new()
+            };
+            writeln!(
+                out,
+                r#"{{"severity":{},"code":{},"message":{}{}}}"#,
+                json_string(diagnostic.get_severity()),
+                json_string(diagnostic.get_code()),
+                json_string(diagnostic.get_message()),
+                location,
+            )?;
+        } else {
+            let location = if has_location {
+                format!("{}:{}: ", diagnostic.get_line(), diagnostic.get_column())
+            } else {
+                String::new()
+            };
+            writeln!(
+                out,
+                "{}[{}]: {}{}",
+                diagnostic.get_severity(),
+                diagnostic.get_code(),
+                location,
+                diagnostic.get_message(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints the bucket each import is attributed to, e.g. `trace: tokio -> feature "rt"`.
+fn print_import_trace(out: &mut impl Write, rust_imports: &parser::RustImports) -> io::Result<()> {
+    for (imports, bucket) in [
+        (&rust_imports.imports, "regular"),
+        (&rust_imports.test_imports, "test"),
+        (&rust_imports.coverage_imports, "coverage"),
+        (&rust_imports.miri_imports, "miri"),
+    ] {
+        for import in imports {
+            writeln!(out, "trace: {} -> {}", import, bucket)?;
+        }
+    }
+
+    for (feature_imports, negated) in [
+        (&rust_imports.feature_imports, false),
+        (&rust_imports.anti_feature_imports, true),
+    ] {
+        let mut feature_imports: Vec<_> = feature_imports.iter().collect();
+        feature_imports.sort();
+        for (feature, imports) in feature_imports {
+            let prefix = if negated { "not " } else { "" };
+            for import in imports {
+                writeln!(out, "trace: {} -> {}feature {:?}", import, prefix, feature)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Formats a cfg scope and its children as a JSON object, e.g.
+/// `{"predicate":"test","bucket":"test","condition":"","imports":["mockall"],"children":[]}`.
+/// The root's predicate is null.
+fn cfg_tree_json(scope: &parser::CfgScope) -> String {
+    let predicate = match &scope.predicate {
+        Some(predicate) => json_string(predicate),
+        None => "null".to_string(),
+    };
+    let imports: Vec<String> = scope.imports.iter().map(|s| json_string(s)).collect();
+    let children: Vec<String> = scope.children.iter().map(cfg_tree_json).collect();
+    format!(
+        r#"{{"predicate":{},"bucket":{},"condition":{},"imports":[{}],"children":[{}]}}"#,
+        predicate,
+        json_string(scope.bucket),
+        json_string(&scope.condition),
+        imports.join(","),
+        children.join(","),
+    )
+}
+
+/// Quotes and escapes a string as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn print_text(out: &mut impl Write, rust_imports: &parser::RustImports) -> io::Result<()> {
+    writeln!(out, "Imports:")?;
+    for import in &rust_imports.imports {
+        writeln!(out, "  {}", import)?;
+    }
+    if let Some(content_hash) = &rust_imports.content_hash {
+        writeln!(out, "Content hash: {}", content_hash)?;
+    }
+    if !rust_imports.referenced_features.is_empty() {
+        writeln!(out, "Features:")?;
+        for feature in &rust_imports.referenced_features {
+            writeln!(out, "  {}", feature)?;
+        }
+    }
+    Ok(())
+}
+
+fn print_cargo_toml(out: &mut impl Write, rust_imports: &parser::RustImports) -> io::Result<()> {
+    // NOTE: these are crate names, which have underscores where the package name might have hyphens
+    let is_dep = |import: &&String| !BUILTINS.contains(&import.as_str());
+    writeln!(out, "[dependencies]")?;
+    for import in rust_imports.imports.iter().filter(is_dep) {
+        writeln!(out, "{} = \"*\"", import)?;
+    }
+    writeln!(out)?;
+    writeln!(out, "[dev-dependencies]")?;
+    for import in rust_imports.test_imports.iter().filter(is_dep) {
+        writeln!(out, "{} = \"*\"", import)?;
+    }
+
+    // only a hint, since making these deps optional would also take them out of [dependencies]
+    if !rust_imports.feature_imports.is_empty() {
+        let mut features: Vec<_> = rust_imports.feature_imports.iter().collect();
+        features.sort();
+        writeln!(out)?;
+        writeln!(out, "# [features]")?;
+        for (feature, imports) in features {
+            let deps: Vec<String> = imports
+                .iter()
+                .filter(is_dep)
+                .map(|import| format!("\"dep:{}\"", import))
+                .collect();
+            writeln!(out, "# {} = [{}]", feature, deps.join(", "))?;
+        }
+    }
+    Ok(())
+}
+
+/// Packs the boolean hints into the bits of a number, starting from the least significant:
+///
+/// | bit | hint                      |
+/// |-----|---------------------------|
+/// | 0   | has_main                  |
+/// | 1   | has_test                  |
+/// | 2   | has_proc_macro            |
+/// | 3   | has_repr_c                |
+/// | 4   | has_macro_generated_items |
+/// | 5   | has_coverage_off          |
+/// | 6   | has_unresolvable_derive   |
+/// | 7   | is_no_std                 |
+///
+/// New hints are only ever appended, so the bits of existing hints stay the same.
+fn print_hints_bitfield(out: &mut impl Write, hints: &Hints) -> io::Result<()> {
+    let bits = [
+        hints.has_main,
+        hints.has_test,
+        hints.has_proc_macro,
+        hints.has_repr_c,
+        hints.has_macro_generated_items,
+        hints.has_coverage_off,
+        hints.has_unresolvable_derive,
+        hints.is_no_std,
+    ];
+    let bitfield = bits
+        .iter()
+        .enumerate()
+        .filter(|(_, &set)| set)
+        .fold(0u32, |bitfield, (bit, _)| bitfield | 1 << bit);
+    writeln!(out, "{:#x}", bitfield)
+}
+
+fn print_imports_per_line(
+    out: &mut impl Write,
+    rust_imports: &parser::RustImports,
+) -> io::Result<()> {
+    let buckets = [
+        ("imports", &rust_imports.imports),
+        ("test_imports", &rust_imports.test_imports),
+        ("coverage_imports", &rust_imports.coverage_imports),
+        ("miri_imports", &rust_imports.miri_imports),
+        ("keep_imports", &rust_imports.keep_imports),
+        ("reexports", &rust_imports.reexports),
+        ("workspace_imports", &rust_imports.workspace_imports),
+        ("external_imports", &rust_imports.external_imports),
+    ];
+
+    let mut lines: Vec<String> = buckets
+        .iter()
+        .flat_map(|(bucket, imports)| {
+            imports
+                .iter()
+                .map(move |import| format!("{} {}", bucket, import))
+        })
+        .collect();
+    // e.g. `feature:serde serde` and `anti_feature:std libm`
+    for (prefix, feature_imports) in [
+        ("feature", &rust_imports.feature_imports),
+        ("anti_feature", &rust_imports.anti_feature_imports),
+    ] {
+        for (feature, imports) in feature_imports {
+            for import in imports {
+                lines.push(format!("{}:{} {}", prefix, feature, import));
+            }
+        }
+    }
+    lines.sort();
+
+    for line in lines {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Answers length-prefixed requests from `input` until it is closed, writing length-prefixed
+/// responses to `output`.
+fn serve_stream_proto(
+    input: &mut impl Read,
+    output: &mut impl Write,
+    options: &RustImportsOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut buf: Vec<u8> = vec![0; 1024];
+    const SF32: usize = std::mem::size_of::<u32>();
+
+    loop {
+        match input.read_exact(&mut buf[..SF32]) {
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                // EOF: parent process finished
+                break;
+            }
+            res => res?,
+        }
+        let size = CodedInputStream::from_bytes(&buf[..SF32]).read_uint32()? as usize;
+        if size > buf.len() {
+            // grow buffer as needed
+            buf = vec![0; size];
+        }
+
+        input.read_exact(&mut buf[..size])?;
+        let request: Request = protobuf::parse_from_bytes(&buf[..size])?;
+
+        if let Some(kind) = request.kind {
+            let response: Box<dyn protobuf::Message> = match kind {
+                Request_oneof_kind::rust_imports(request) => {
+                    Box::new(handle_rust_imports_request(request, options)?)
+                }
+                Request_oneof_kind::lockfile_crates(request) => {
+                    Box::new(handle_lockfile_crates_request(request)?)
+                }
+                Request_oneof_kind::cargo_toml(request) => {
+                    Box::new(handle_cargo_toml_request(request)?)
+                }
+            };
+
+            let mut stream = CodedOutputStream::new(output);
+            stream.write_fixed32_no_tag(response.compute_size())?;
+            response.write_to(&mut stream)?;
+            stream.flush()?;
+            // need to flush the underlying output because protobuf doesn't do that for us
+            output.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
 
     match args {
-        Args::OneShot { path } => {
-            let mut rust_imports = parser::parse_imports(path)?;
-            rust_imports.imports.sort();
-
-            println!("Imports:");
-            for import in rust_imports.imports {
-                println!("  {}", import);
-            }
-        }
-        Args::StreamProto => {
-            let mut stdin = std::io::stdin();
-            let mut writer = std::io::stdout();
-            // TODO: avoid opening two stdout handles
-            let mut writer2 = std::io::stdout();
-            let mut stdout = CodedOutputStream::new(&mut writer);
-
-            let mut buf: Vec<u8> = vec![0; 1024];
-            const SF32: usize = std::mem::size_of::<u32>();
-
-            loop {
-                match stdin.read_exact(&mut buf[..SF32]) {
-                    Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
-                        // EOF: parent process finished
-                        break;
-                    }
-                    res => res?,
-                }
-                let size = CodedInputStream::from_bytes(&buf[..SF32]).read_uint32()? as usize;
-                if size > buf.len() {
-                    // grow buffer as needed
-                    buf = vec![0; size];
+        Args::OneShot {
+            path,
+            parse_args,
+            parse_only,
+            format,
+            check,
+            deps,
+            srcs,
+            warnings_as_json,
+            verbosity,
+        } => {
+            let verbosity = verbosity.level();
+            if verbosity >= 2 {
+                eprintln!("parsing {}", path.display());
+            }
+            if parse_only {
+                if !report_syntax(&mut io::stderr().lock(), &path)? {
+                    std::process::exit(1);
                 }
+                return Ok(());
+            }
+
+            let config: parser::ParseConfig = parse_args.try_into()?;
+            let rust_imports = parser::parse_imports(path, &config)?;
+            if verbosity >= 1 {
+                print_diagnostics(
+                    &mut io::stderr().lock(),
+                    &rust_imports.diagnostics,
+                    warnings_as_json,
+                )?;
+            }
+            if verbosity >= 3 {
+                print_import_trace(&mut io::stderr().lock(), &rust_imports)?;
+            }
 
-                stdin.read_exact(&mut buf[..size])?;
-                let request: Request = protobuf::parse_from_bytes(&buf[..size])?;
-
-                if let Some(kind) = request.kind {
-                    let response: Box<dyn protobuf::Message> = match kind {
-                        Request_oneof_kind::rust_imports(request) => {
-                            Box::new(handle_rust_imports_request(request)?)
-                        }
-                        Request_oneof_kind::lockfile_crates(request) => {
-                            Box::new(handle_lockfile_crates_request(request)?)
-                        }
-                        Request_oneof_kind::cargo_toml(request) => {
-                            Box::new(handle_cargo_toml_request(request)?)
-                        }
-                    };
-
-                    stdout.write_fixed32_no_tag(response.compute_size())?;
-                    response.write_to(&mut stdout)?;
-                    stdout.flush()?;
-                    // need to flush the underlying stdout because protobuf doesn't do that for us
-                    writer2.flush()?;
+            if check {
+                let mut crate_imports = vec![rust_imports];
+                for src in srcs {
+                    crate_imports.push(parser::parse_imports(src, &config)?);
+                }
+                if !check_deps(&mut io::stderr().lock(), &crate_imports, &deps)? {
+                    std::process::exit(1);
                 }
+                return Ok(());
+            }
+
+            let mut stdout = io::stdout().lock();
+            if let Some(cfg_tree) = &rust_imports.cfg_tree {
+                writeln!(stdout, "{}", cfg_tree_json(cfg_tree))?;
+                return Ok(());
+            }
+            match format {
+                Format::Text => print_text(&mut stdout, &rust_imports)?,
+                Format::CargoToml => print_cargo_toml(&mut stdout, &rust_imports)?,
+                Format::ImportsPerLine => print_imports_per_line(&mut stdout, &rust_imports)?,
+                Format::HintsBitfield => print_hints_bitfield(&mut stdout, &rust_imports.hints)?,
             }
         }
+        Args::ReportSynVersion => {
+            println!("{}", option_env!("SYN_VERSION").unwrap_or("unknown"));
+        }
+        Args::StreamProto {
+            parse_args,
+            only,
+            skip,
+            max_imports,
+            format,
+            verbosity,
+        } => {
+            let options = RustImportsOptions {
+                config: parse_args.try_into()?,
+                filter: ResponseFilter::new(only, skip)?,
+                max_imports,
+                format,
+                verbosity: verbosity.level(),
+            };
+
+            serve_stream_proto(&mut io::stdin().lock(), &mut io::stdout().lock(), &options)?;
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use protobuf::Message;
+
+    /// Writes the source to a file of its own in the temp dir and returns its path.
+    fn write_source(name: &str, source: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let path = std::env::temp_dir().join(format!("{}_{}.rs", name, std::process::id()));
+        std::fs::write(&path, source)?;
+        Ok(path)
+    }
+
+    fn stream_proto_options(args: &[&str]) -> Result<RustImportsOptions, Box<dyn Error>> {
+        let args = ["rust_parser", "stream-proto"].iter().chain(args);
+        match Args::try_parse_from(args)? {
+            Args::StreamProto {
+                parse_args,
+                only,
+                skip,
+                max_imports,
+                format,
+                verbosity,
+            } => Ok(RustImportsOptions {
+                config: parse_args.try_into()?,
+                filter: ResponseFilter::new(only, skip)?,
+                max_imports,
+                format,
+                verbosity: verbosity.level(),
+            }),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Sends a rust imports request for each file through serve_stream_proto, the same way gazelle
+    /// does, and returns the responses.
+    fn stream_proto(
+        paths: &[&Path],
+        args: &[&str],
+    ) -> Result<Vec<RustImportsResponse>, Box<dyn Error>> {
+        let mut input = Vec::new();
+        for path in paths {
+            let mut rust_imports = RustImportsRequest::default();
+            rust_imports.set_file_path(path.to_string_lossy().to_string());
+            let mut request = Request::default();
+            request.set_rust_imports(rust_imports);
+            let bytes = request.write_to_bytes()?;
+            input.extend((bytes.len() as u32).to_le_bytes());
+            input.extend(bytes);
+        }
+
+        let mut output = Vec::new();
+        serve_stream_proto(&mut &input[..], &mut output, &stream_proto_options(args)?)?;
+
+        let mut responses = Vec::new();
+        let mut rest = &output[..];
+        while !rest.is_empty() {
+            let (size, tail) = rest.split_at(4);
+            let size = u32::from_le_bytes(size.try_into()?) as usize;
+            let (bytes, tail) = tail.split_at(size);
+            responses.push(protobuf::parse_from_bytes(bytes)?);
+            rest = tail;
+        }
+        Ok(responses)
+    }
+
+    fn stream_proto_one(path: &Path, args: &[&str]) -> Result<RustImportsResponse, Box<dyn Error>> {
+        Ok(stream_proto(&[path], args)?.remove(0))
+    }
+
+    #[test]
+    fn proto_v2_test() -> Result<(), Box<dyn Error>> {
+        let path = write_source(
+            "proto_v2",
+            r#"
+use regular::Thing;
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Thing {}
+
+#[cfg(feature = "log")]
+fn trace() {
+    log::trace!("both");
+}
+
+fn always() {
+    log::info!("both");
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use test_crate::check;
+}
+"#,
+        )?;
+        let response = stream_proto_one(&path, &["--format", "proto-v2"])?;
+        assert!(response.get_imports().is_empty());
+        assert!(response.get_feature_imports().is_empty());
+
+        let all_imports: Vec<(&str, ImportKind, &str)> = response
+            .get_all_imports()
+            .iter()
+            .map(|import| (import.get_name(), import.get_kind(), import.get_condition()))
+            .collect();
+        // each crate is listed once, and only feature-only crates have a condition
+        assert_eq!(
+            all_imports,
+            vec![
+                ("log", ImportKind::REGULAR, ""),
+                ("regular", ImportKind::REGULAR, ""),
+                ("serde", ImportKind::REGULAR, "feature = \"serde\""),
+                ("test_crate", ImportKind::TEST, "not(feature = \"std\")"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn report_syntax_test() -> Result<(), Box<dyn Error>> {
+        let path = write_source("report_syntax", "fn f() {}\nfn g() { let = 1; }\n")?;
+        let mut out = Vec::new();
+        // one-shot --parse-only exits with 1 when this is false
+        assert!(!report_syntax(&mut out, &path)?);
+        let out = String::from_utf8(out)?;
+        assert!(
+            out.starts_with(&format!("{}:2:14: ", path.display())),
+            "{}",
+            out
+        );
+
+        let path = write_source("report_syntax_ok", "fn f() {}\n")?;
+        let mut out = Vec::new();
+        assert!(report_syntax(&mut out, &path)?);
+        assert!(out.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn max_imports_test() -> Result<(), Box<dyn Error>> {
+        let path = write_source(
+            "max_imports",
+            r#"
+#[allow(unused_imports)]
+use a::A;
+#[allow(unused_imports)]
+use c::C;
+use b::B;
+
+#[cfg(feature = "x")]
+fn x() {
+    c::call();
+}
+
+#[cfg(test)]
+mod tests {
+    use d::D;
+    use e::E;
+    use f::F;
+}
+"#,
+        )?;
+
+        // exactly at the cap, nothing is dropped
+        let response = stream_proto_one(&path, &["--max-imports", "3"])?;
+        assert!(!response.get_truncated());
+        assert_eq!(response.get_imports(), ["a", "b", "c"]);
+        assert_eq!(response.get_test_imports(), ["d", "e", "f"]);
+
+        let response = stream_proto_one(&path, &["--max-imports", "2"])?;
+        assert!(response.get_truncated());
+        assert_eq!(response.get_imports(), ["a", "b"]);
+        assert_eq!(response.get_test_imports(), ["d", "e"]);
+        // the lists that describe the imports only mention the ones that are left
+        assert_eq!(response.get_keep_imports(), ["a"]);
+        assert!(response.get_feature_imports().is_empty());
+        let import_sources: Vec<&str> = response
+            .get_import_sources()
+            .iter()
+            .map(|import_source| import_source.get_name())
+            .collect();
+        assert_eq!(import_sources, ["a", "b", "d", "e"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn imports_per_line_test() -> Result<(), Box<dyn Error>> {
+        let path = write_source(
+            "imports_per_line",
+            r#"
+use regular::Thing;
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Thing {}
+
+#[cfg(not(feature = "std"))]
+fn sqrt() {
+    libm::sqrt(2.0);
+}
+
+#[cfg(coverage)]
+fn cover() {
+    coverage_crate::mark();
+}
+
+#[cfg(miri)]
+fn miri() {
+    miri_crate::check();
+}
+"#,
+        )?;
+        let rust_imports = parser::parse_imports(path, &parser::ParseConfig::default())?;
+        let mut out = Vec::new();
+        print_imports_per_line(&mut out, &rust_imports)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "anti_feature:std libm
+coverage_imports coverage_crate
+feature:serde serde
+imports libm
+imports regular
+imports serde
+miri_imports miri_crate
+"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn proto_v2_provenance_test() -> Result<(), Box<dyn Error>> {
+        let path = write_source(
+            "proto_v2_provenance",
+            r#"
+extern crate both;
+extern crate extern_only;
+
+use both::Thing;
+use use_only::Other;
+
+fn f() {
+    path_only::call();
+}
+
+macro_rules! call {
+    () => {
+        macro_only::call()
+    };
+}
+"#,
+        )?;
+        let response = stream_proto_one(&path, &["--format", "proto-v2"])?;
+        let all_imports: Vec<(&str, bool, bool)> = response
+            .get_all_imports()
+            .iter()
+            .map(|import| {
+                (
+                    import.get_name(),
+                    import.get_via_extern_crate(),
+                    import.get_via_use(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            all_imports,
+            vec![
+                ("both", true, true),
+                ("extern_only", true, false),
+                ("macro_only", false, false),
+                ("path_only", false, false),
+                ("use_only", false, true),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_deps_test() -> Result<(), Box<dyn Error>> {
+        let lib = write_source("check_deps_lib", "mod a;\nuse serde::Serialize;\n")?;
+        let a = write_source("check_deps_a", "fn f() { tokio::spawn(async {}); }\n")?;
+        let parse = |path: &PathBuf| parser::parse_imports(path.clone(), &Default::default());
+        let deps = ["serde".to_string(), "tokio".to_string()];
+
+        // a dep used by another file of the crate isn't unused
+        let mut out = Vec::new();
+        assert!(check_deps(&mut out, &[parse(&lib)?, parse(&a)?], &deps)?);
+        assert!(out.is_empty());
+
+        let mut out = Vec::new();
+        assert!(!check_deps(&mut out, &[parse(&lib)?], &deps[1..])?);
+        assert_eq!(
+            String::from_utf8(out)?,
+            "Missing deps:\n  serde\nUnused deps:\n  tokio\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn response_filter_test() -> Result<(), Box<dyn Error>> {
+        let path = write_source(
+            "response_filter",
+            r#"
+use serde::Serialize;
+
+fn main() {}
+
+#[cfg(test)]
+mod tests {
+    use d::D;
+}
+"#,
+        )?;
+
+        let response = stream_proto_one(&path, &["--only", "imports,has_main"])?;
+        assert_eq!(response.get_imports(), ["serde"]);
+        assert!(response.get_test_imports().is_empty());
+        assert!(response.get_import_sources().is_empty());
+        assert!(response.get_hints().get_has_main());
+
+        let response = stream_proto_one(&path, &["--skip", "imports,has_main"])?;
+        assert!(response.get_imports().is_empty());
+        assert_eq!(response.get_test_imports(), ["d"]);
+        assert!(!response.get_hints().get_has_main());
+
+        assert!(stream_proto_options(&["--only", "imports,unknown"]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn one_shot_formats_test() -> Result<(), Box<dyn Error>> {
+        let path = write_source(
+            "one_shot_formats",
+            r#"
+use serde::Serialize;
+use std::collections::HashMap;
+
+fn parse(s: &str) -> u32 {
+    u32::from_str_radix(s, 10).unwrap_or(core::u32::MAX)
+}
+
+#[cfg(feature = "log")]
+fn trace() {
+    log::trace!("trace");
+}
+
+fn main() {}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn t() {
+        std::assert!(true);
+        d::check();
+    }
+}
+"#,
+        )?;
+        let rust_imports = parser::parse_imports(path, &parser::ParseConfig::default())?;
+
+        let mut out = Vec::new();
+        print_text(&mut out, &rust_imports)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "Imports:\n  core\n  log\n  serde\n  std\n  u32\n"
+        );
+
+        // builtins aren't deps
+        let mut out = Vec::new();
+        print_cargo_toml(&mut out, &rust_imports)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            r#"[dependencies]
+log = "*"
+serde = "*"
+
+[dev-dependencies]
+d = "*"
+
+# [features]
+# log = ["dep:log"]
+"#
+        );
+
+        // has_main and has_test
+        let mut out = Vec::new();
+        print_hints_bitfield(&mut out, &rust_imports.hints)?;
+        assert_eq!(String::from_utf8(out)?, "0x3\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn config_file_test() -> Result<(), Box<dyn Error>> {
+        let path = write_source("config_file", "use zeta::Z;\nuse core_lib::C;\n")?;
+        let config_file =
+            std::env::temp_dir().join(format!("config_file_{}.toml", std::process::id()));
+        std::fs::write(
+            &config_file,
+            "workspace_crates = [\"core_lib\"]\nsource_order = true\n",
+        )?;
+        let config_file = config_file.to_string_lossy().to_string();
+
+        let response = stream_proto_one(&path, &["--config-file", &config_file])?;
+        assert_eq!(response.get_imports(), ["zeta", "core_lib"]);
+        assert_eq!(response.get_workspace_imports(), ["core_lib"]);
+
+        // flags that are given override the file
+        let response =
+            stream_proto_one(&path, &["--config-file", &config_file, "--order", "sorted"])?;
+        assert_eq!(response.get_imports(), ["core_lib", "zeta"]);
+        assert_eq!(response.get_workspace_imports(), ["core_lib"]);
+
+        let missing = format!("{}.missing", config_file);
+        let err = stream_proto_options(&["--config-file", &missing])
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with(&missing), "{}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn verbosity_test() -> Result<(), Box<dyn Error>> {
+        assert_eq!(stream_proto_options(&[])?.verbosity, 1);
+        assert_eq!(stream_proto_options(&["-q"])?.verbosity, 0);
+        assert_eq!(stream_proto_options(&["-v"])?.verbosity, 2);
+        assert_eq!(stream_proto_options(&["-vv"])?.verbosity, 3);
+        assert!(stream_proto_options(&["-q", "-v"]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn print_diagnostics_test() -> Result<(), Box<dyn Error>> {
+        let mut located = Diagnostic::default();
+        located.set_severity("warning".to_string());
+        located.set_code("macro_skipped".to_string());
+        located.set_message("too big".to_string());
+        located.set_line(3);
+        located.set_column(5);
+        let mut unlocated = located.clone();
+        unlocated.clear_line();
+        unlocated.clear_column();
+        let diagnostics = [located, unlocated];
+
+        let mut out = Vec::new();
+        print_diagnostics(&mut out, &diagnostics, false)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "warning[macro_skipped]: 3:5: too big\nwarning[macro_skipped]: too big\n"
+        );
+
+        let mut out = Vec::new();
+        print_diagnostics(&mut out, &diagnostics, true)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            r#"{"severity":"warning","code":"macro_skipped","message":"too big","line":3,"column":5}
+{"severity":"warning","code":"macro_skipped","message":"too big"}
+"#
+        );
+
+        Ok(())
+    }
+}
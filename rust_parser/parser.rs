@@ -1,26 +1,117 @@
 #![deny(unused_must_use)]
 
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::error::Error;
+use std::ffi::OsStr;
+use std::fmt;
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use proc_macro2::{Span, TokenStream, TokenTree};
+use serde::Deserialize;
 use syn::parse_file;
+use syn::spanned::Spanned;
 use syn::visit::{self, Visit};
 
-use messages_rust_proto::Hints;
+use messages_rust_proto::{
+    ApiMarker, Diagnostic, Hints, ImportSource, LintLevel, NativeLink, TestFn,
+};
 
+/// Every list of imports is sorted, or in source order with ParseConfig::source_order.
 pub struct RustImports {
     pub hints: Hints,
     pub imports: Vec<String>,
     pub test_imports: Vec<String>,
+    /// imports that are only used under #[cfg(coverage)]
+    pub coverage_imports: Vec<String>,
+    /// imports that are only used under #[cfg(miri)]
+    pub miri_imports: Vec<String>,
+    /// imports that are used under #[cfg(feature = "...")], by feature; these are in the other
+    /// buckets too
+    pub feature_imports: HashMap<String, Vec<String>>,
+    /// imports that are used under #[cfg(not(feature = "..."))], by feature; these are in the other
+    /// buckets too
+    pub anti_feature_imports: HashMap<String, Vec<String>>,
+    /// the feature predicate of each import that is only used under feature cfgs, e.g.
+    /// `feature = "x"` or `any(feature = "x", not(feature = "y"))`
+    pub import_conditions: HashMap<String, String>,
+    /// every feature referred to by a cfg anywhere in the file, sorted, if requested with
+    /// ParseConfig::list_features
+    pub referenced_features: Vec<String>,
+    /// `extern crate` declarations of alloc, core or std in a #![no_std] file, sorted; these are
+    /// also in the other buckets
+    pub std_externs: Vec<String>,
+    /// macros that weren't scanned for imports because they have more than
+    /// ParseConfig::max_macro_tokens tokens, in source order
+    pub macros_skipped: Vec<String>,
+    /// warnings about things that may make the imports incomplete, in source order
+    pub diagnostics: Vec<Diagnostic>,
     pub extern_mods: Vec<String>,
+    /// #[path = "..."] attributes of extern mods, by mod name
+    pub extern_mod_paths: HashMap<String, String>,
+    /// imports from uses under #[allow(unused_imports)], which should be kept even if they look
+    /// unused
+    pub keep_imports: Vec<String>,
+    /// imports that are re-exported with `pub use`
+    pub reexports: Vec<String>,
+    /// hash of the source the imports were parsed from, if requested with
+    /// ParseConfig::emit_hash
+    pub content_hash: Option<String>,
+    /// all imports that are in ParseConfig::workspace_crates
+    pub workspace_imports: Vec<String>,
+    /// all imports that are not in ParseConfig::workspace_crates, if it is set
+    pub external_imports: Vec<String>,
+    /// #[test] functions, if requested with ParseConfig::emit_test_names
+    pub test_fns: Vec<TestFn>,
+    /// for each import in any bucket, whether it is named by a `use` or `extern crate` rather than
+    /// only in qualified paths
+    pub import_sources: Vec<ImportSource>,
+    /// paths of the mods containing #[test] or #[bench] functions, if requested with
+    /// ParseConfig::group_test_by_mod
+    pub test_mods: Vec<String>,
+    /// #[link(name = "...")] libraries of extern blocks, in source order
+    pub native_links: Vec<NativeLink>,
+    /// public items as `<kind> <path>`, e.g. `fn config::load`, sorted, if requested with
+    /// ParseConfig::emit_public_api
+    pub public_api: Vec<String>,
+    /// the cfg scopes of the file, if requested with ParseConfig::emit_cfg_tree
+    pub cfg_tree: Option<CfgScope>,
 }
 
-pub fn parse_imports(path: PathBuf) -> Result<RustImports, Box<dyn Error>> {
+/// A scope with its own #[cfg], e.g. a #[cfg(test)] mod or a #[cfg(feature = "x")] use, and the
+/// imports attributed within it.
+#[derive(Debug, Default)]
+pub struct CfgScope {
+    /// the cfg predicate, e.g. `feature = "x"`, or None for the whole file
+    pub predicate: Option<String>,
+    /// the bucket the imports within the scope are reported in: `regular`, `test`, `coverage` or
+    /// `miri`
+    pub bucket: &'static str,
+    /// the feature gates the imports within the scope are reported under, e.g. `feature = "x"` or
+    /// `all(feature = "x", not(feature = "y"))`, or empty
+    pub condition: String,
+    /// imports in this scope but not in any of its children, sorted
+    pub imports: Vec<String>,
+    /// in source order
+    pub children: Vec<CfgScope>,
+}
+
+impl RustImports {
+    /// Imports in every bucket. A crate is never in imports and another bucket, but can be in more
+    /// than one of the others.
+    pub fn all_imports(&self) -> impl Iterator<Item = &String> {
+        self.imports
+            .iter()
+            .chain(&self.test_imports)
+            .chain(&self.coverage_imports)
+            .chain(&self.miri_imports)
+    }
+}
+
+fn read_file(path: &PathBuf) -> Result<String, Box<dyn Error>> {
     // TODO: stream from the file instead of loading it all into memory?
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(err) => {
             eprintln!(
                 "Could not open file {}: {}",
@@ -33,50 +124,502 @@ pub fn parse_imports(path: PathBuf) -> Result<RustImports, Box<dyn Error>> {
     };
     let mut content = String::new();
     file.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Checks that the file is syntactically valid without extracting anything from it. Syntax errors
+/// are returned as a SyntaxError.
+pub fn check_syntax(path: PathBuf) -> Result<(), Box<dyn Error>> {
+    parse_file(&read_file(&path)?).map_err(|err| {
+        let start = err.span().start();
+        SyntaxError {
+            line: start.line,
+            // proc-macro2 columns are 0-based, unlike rustc's
+            column: start.column + 1,
+            message: err.to_string(),
+        }
+    })?;
+    Ok(())
+}
+
+/// A syntax error and where it starts, with 1-based line and column.
+#[derive(Debug)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for SyntaxError {}
+
+/// Options that change what the parser extracts.
+/// Can also be read from a TOML file with the same field names; missing fields are defaults.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParseConfig {
+    /// attributes that mark a top-level function as the entry point even if it isn't named main,
+    /// e.g. `rocket::launch`; as a best effort, bare attributes are resolved through top-level
+    /// uses, so `#[main]` after `use tokio::main;` matches `tokio::main`
+    pub entry_attrs: Vec<String>,
+    /// whether to hash the source so callers can detect unchanged files
+    pub emit_hash: bool,
+    /// crates that are built from this workspace rather than from a registry; if set, imports are
+    /// also partitioned by which of the two they come from
+    pub workspace_crates: Vec<String>,
+    /// whether to collect the names and modifiers of #[test] functions
+    pub emit_test_names: bool,
+    /// whether to lowercase the first letter of uppercase candidates instead of dropping them, so
+    /// that crates only referred to by an uppercase alias like `extern crate Foo` are kept; this
+    /// also turns type names like `String` into bogus crate names like `string`
+    pub normalize_case: bool,
+    /// whether to collect the mods that contain #[test] or #[bench] functions
+    pub group_test_by_mod: bool,
+    /// whether to list imports in the order they first appear in the file instead of sorted
+    pub source_order: bool,
+    /// whether a file with a main function is a custom test harness (e.g. `harness = false`), so
+    /// that all of its imports are test imports; has_main is still set
+    pub treat_bin_as_test: bool,
+    /// whether to collect every feature that a cfg in the file refers to
+    pub list_features: bool,
+    /// name of the crate the file belongs to, which is left out of the imports since the crate can
+    /// refer to itself by name
+    pub crate_name: Option<String>,
+    /// whether to infer crate_name from the path if it isn't set; see infer_crate_name
+    pub crate_name_from_path: bool,
+    /// macros with more tokens than this aren't scanned for imports, to bound the cost of the
+    /// heuristics on generated code
+    pub max_macro_tokens: Option<usize>,
+    /// whether to leave out test-only code entirely, without even reporting test_imports; test-only
+    /// mods and functions aren't visited, so hints like has_test only reflect the rest of the file
+    pub prod_only: bool,
+    /// whether to collect the public functions, structs, enums and traits
+    pub emit_public_api: bool,
+    /// whether to scan quote!, quote_spanned! and parse_quote! bodies for imports; these usually
+    /// describe the code a proc macro generates, whose crates are deps of the caller instead
+    pub scan_quote_bodies: bool,
+    /// whether to collect the tree of cfg scopes with the imports attributed within each, to audit
+    /// how imports are bucketed
+    pub emit_cfg_tree: bool,
+}
+
+pub fn parse_imports(path: PathBuf, config: &ParseConfig) -> Result<RustImports, Box<dyn Error>> {
+    let content = read_file(&path)?;
+    let content_hash = config.emit_hash.then(|| content_hash(content.as_bytes()));
 
     let ast = parse_file(&content)?;
-    let mut visitor = AstVisitor::default();
-    visitor.visit_file(&ast);
+    let analysis = analyze(&ast, config);
 
-    let test_imports = visitor
-        .test_imports
-        .difference(&visitor.imports)
-        .cloned()
+    let mut rust_imports = RustImports {
+        hints: analysis.hints,
+        imports: to_strings(analysis.imports),
+        test_imports: to_strings(analysis.test_imports),
+        coverage_imports: to_strings(analysis.coverage_imports),
+        miri_imports: to_strings(analysis.miri_imports),
+        feature_imports: analysis
+            .feature_imports
+            .into_iter()
+            .map(|(feature, imports)| (feature, to_strings(imports)))
+            .collect(),
+        anti_feature_imports: analysis
+            .anti_feature_imports
+            .into_iter()
+            .map(|(feature, imports)| (feature, to_strings(imports)))
+            .collect(),
+        extern_mods: analysis.extern_mods,
+        extern_mod_paths: analysis.extern_mod_paths,
+        keep_imports: to_strings(analysis.keep_imports),
+        reexports: to_strings(analysis.reexports),
+        content_hash,
+        workspace_imports: Vec::new(),
+        external_imports: Vec::new(),
+        test_fns: analysis.test_fns,
+        import_sources: Vec::new(),
+        test_mods: analysis.test_mods,
+        public_api: analysis.public_api,
+        native_links: analysis.native_links,
+        referenced_features: analysis.referenced_features,
+        std_externs: analysis.std_externs,
+        macros_skipped: analysis.macros_skipped,
+        diagnostics: analysis.diagnostics,
+        cfg_tree: analysis.cfg_tree,
+        import_conditions: HashMap::new(),
+    };
+    rust_imports.import_conditions = import_conditions(
+        analysis
+            .feature_conditions
+            .into_iter()
+            .map(|(condition, imports)| (condition, to_strings(imports))),
+        &to_strings(analysis.ungated_imports),
+    );
+    let crate_name = match &config.crate_name {
+        Some(crate_name) => Some(crate_name.clone()),
+        None if config.crate_name_from_path => infer_crate_name(&path),
+        None => None,
+    };
+    if let Some(crate_name) = crate_name {
+        exclude_import(&mut rust_imports, &crate_name);
+    }
+    if config.treat_bin_as_test && rust_imports.hints.has_main {
+        let imports = std::mem::take(&mut rust_imports.imports);
+        rust_imports.test_imports.extend(imports);
+    }
+    rust_imports.import_sources = import_sources(
+        &rust_imports,
+        &to_strings(analysis.extern_crate_imports),
+        &to_strings(analysis.use_imports),
+    );
+    if !config.workspace_crates.is_empty() {
+        partition_by_source(&mut rust_imports, &config.workspace_crates);
+    }
+
+    let positions: Option<HashMap<&String, usize>> = config.source_order.then(|| {
+        let order = analysis.import_order.iter().enumerate();
+        order.map(|(position, import)| (import, position)).collect()
+    });
+    for imports in [
+        &mut rust_imports.imports,
+        &mut rust_imports.test_imports,
+        &mut rust_imports.coverage_imports,
+        &mut rust_imports.miri_imports,
+        &mut rust_imports.keep_imports,
+        &mut rust_imports.reexports,
+        &mut rust_imports.workspace_imports,
+        &mut rust_imports.external_imports,
+    ]
+    .into_iter()
+    .chain(rust_imports.feature_imports.values_mut())
+    .chain(rust_imports.anti_feature_imports.values_mut())
+    {
+        match &positions {
+            Some(positions) => imports.sort_by_key(|import| positions.get(import)),
+            None => imports.sort(),
+        }
+    }
+
+    Ok(rust_imports)
+}
+
+/// Guesses the name of the library crate a file belongs to from the Cargo layout: the directory
+/// containing the nearest `src` ancestor names the package, with hyphens turned into underscores,
+/// e.g. `my-crate/src/a/b.rs` -> `my_crate`. Binaries (`src/main.rs` and `src/bin/`) are skipped,
+/// since they depend on the library of the same name, as are paths without a `src` directory.
+fn infer_crate_name(path: &Path) -> Option<String> {
+    let components: Vec<&OsStr> = path.iter().collect();
+    let src = components
+        .iter()
+        .rposition(|component| *component == "src")?;
+    if src == 0 {
+        return None;
+    }
+
+    let in_src = &components[src + 1..];
+    if in_src == ["main.rs"] || in_src.first() == Some(&OsStr::new("bin")) {
+        return None;
+    }
+
+    let package = components[src - 1].to_str()?;
+    Some(package.replace('-', "_"))
+}
+
+/// Removes the import from every bucket.
+fn exclude_import(rust_imports: &mut RustImports, import: &str) {
+    for imports in [
+        &mut rust_imports.imports,
+        &mut rust_imports.test_imports,
+        &mut rust_imports.coverage_imports,
+        &mut rust_imports.miri_imports,
+        &mut rust_imports.keep_imports,
+        &mut rust_imports.reexports,
+    ]
+    .into_iter()
+    .chain(rust_imports.feature_imports.values_mut())
+    .chain(rust_imports.anti_feature_imports.values_mut())
+    {
+        imports.retain(|other| other != import);
+    }
+    rust_imports
+        .feature_imports
+        .retain(|_, imports| !imports.is_empty());
+    rust_imports
+        .anti_feature_imports
+        .retain(|_, imports| !imports.is_empty());
+}
+
+/// The feature predicates of the imports that are never used outside a feature cfg.
+fn import_conditions(
+    feature_conditions: impl Iterator<Item = (String, Vec<String>)>,
+    ungated_imports: &[String],
+) -> HashMap<String, String> {
+    let mut predicates: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for (condition, imports) in feature_conditions {
+        for import in imports
+            .into_iter()
+            .filter(|import| !ungated_imports.contains(import))
+        {
+            predicates
+                .entry(import)
+                .or_default()
+                .insert(condition.clone());
+        }
+    }
+
+    predicates
+        .into_iter()
+        .map(|(import, predicates)| {
+            let predicates: Vec<String> = predicates.into_iter().collect();
+            let condition = if predicates.len() == 1 {
+                predicates[0].clone()
+            } else {
+                format!("any({})", predicates.join(", "))
+            };
+            (import, condition)
+        })
+        .collect()
+}
+
+/// Whether each import is declared by a use or extern crate, sorted by crate.
+fn import_sources(
+    rust_imports: &RustImports,
+    extern_crate_imports: &[String],
+    use_imports: &[String],
+) -> Vec<ImportSource> {
+    let mut import_sources: Vec<ImportSource> = rust_imports
+        .all_imports()
+        .map(|import| {
+            let via_extern_crate = extern_crate_imports.contains(import);
+            let via_use = use_imports.contains(import);
+            let mut import_source = ImportSource::default();
+            import_source.set_name(import.clone());
+            import_source.set_has_use(via_extern_crate || via_use);
+            import_source.set_via_extern_crate(via_extern_crate);
+            import_source.set_via_use(via_use);
+            import_source
+        })
         .collect();
+    import_sources.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+    import_sources.dedup_by(|a, b| a.get_name() == b.get_name());
+    import_sources
+}
+
+/// Splits every import bucket into workspace_imports and external_imports.
+fn partition_by_source(rust_imports: &mut RustImports, workspace_crates: &[String]) {
+    // keep_imports and reexports are subsets of the other buckets
+    let all_imports: BTreeSet<&String> = rust_imports.all_imports().collect();
+    let (workspace_imports, external_imports) = all_imports
+        .into_iter()
+        .cloned()
+        .partition(|import| workspace_crates.contains(import));
+    rust_imports.workspace_imports = workspace_imports;
+    rust_imports.external_imports = external_imports;
+}
+
+/// 64-bit FNV-1a of the content, as 16 hex digits. This is only meant for change detection, not
+/// for anything that needs collision resistance.
+fn content_hash(bytes: &[u8]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let hash = bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Same as RustImports, but borrowing the identifiers from the parsed file instead of copying them,
+/// for callers that have their own syn::File and want to avoid the allocations or inspect spans.
+pub struct Analysis<'ast> {
+    pub hints: Hints,
+    pub imports: IdentSet<'ast>,
+    pub test_imports: IdentSet<'ast>,
+    pub coverage_imports: IdentSet<'ast>,
+    pub miri_imports: IdentSet<'ast>,
+    pub feature_imports: HashMap<String, IdentSet<'ast>>,
+    pub anti_feature_imports: HashMap<String, IdentSet<'ast>>,
+    pub extern_mods: Vec<String>,
+    pub extern_mod_paths: HashMap<String, String>,
+    pub keep_imports: IdentSet<'ast>,
+    pub reexports: IdentSet<'ast>,
+    pub test_fns: Vec<TestFn>,
+    /// imports named by a `use` or `extern crate`, in any bucket
+    pub declared_imports: IdentSet<'ast>,
+    /// the subset of declared_imports named by an `extern crate`
+    pub extern_crate_imports: IdentSet<'ast>,
+    /// the subset of declared_imports named by a `use`
+    pub use_imports: IdentSet<'ast>,
+    /// imports used at least once outside a feature cfg
+    pub ungated_imports: IdentSet<'ast>,
+    /// imports used under feature cfgs, by the predicate of all the feature gates they're behind,
+    /// e.g. `all(feature = "a", feature = "b")`
+    pub feature_conditions: HashMap<String, IdentSet<'ast>>,
+    /// sorted, with an empty path for the root
+    pub test_mods: Vec<String>,
+    /// every import in the order it first appears in the file, if requested with
+    /// ParseConfig::source_order
+    pub import_order: Vec<String>,
+    /// sorted
+    pub referenced_features: Vec<String>,
+    /// sorted
+    pub std_externs: Vec<String>,
+    pub macros_skipped: Vec<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// sorted
+    pub public_api: Vec<String>,
+    pub native_links: Vec<NativeLink>,
+    pub cfg_tree: Option<CfgScope>,
+}
+
+pub fn analyze<'ast>(ast: &'ast syn::File, config: &ParseConfig) -> Analysis<'ast> {
+    let mut visitor = AstVisitor::new(config);
+    visitor.visit_file(ast);
+
+    let cfg_tree = visitor
+        .open_cfg_scopes
+        .pop()
+        .map(|root| visitor.close_cfg_scope(root));
+
+    let mut test_mods: Vec<String> = visitor.test_mods.into_iter().collect();
+    test_mods.sort();
+
+    // normalize before taking the differences, so that e.g. `Foo` and `foo` are the same crate
+    let filter = |imports| filter_imports(imports, config.normalize_case);
+    let imports = filter(visitor.imports);
+    let without_regular = |other: IdentSet<'ast>| -> IdentSet<'ast> {
+        filter(other).difference(&imports).cloned().collect()
+    };
+    let test_imports = without_regular(visitor.test_imports);
+    let coverage_imports = without_regular(visitor.coverage_imports);
+    let miri_imports = without_regular(visitor.miri_imports);
 
-    Ok(RustImports {
+    Analysis {
         hints: visitor.hints,
-        imports: filter_imports(visitor.imports),
-        test_imports: filter_imports(test_imports),
+        imports,
+        test_imports,
+        coverage_imports,
+        miri_imports,
+        feature_imports: visitor
+            .feature_imports
+            .into_iter()
+            .map(|(feature, imports)| (feature, filter(imports)))
+            .collect(),
+        anti_feature_imports: visitor
+            .anti_feature_imports
+            .into_iter()
+            .map(|(feature, imports)| (feature, filter(imports)))
+            .collect(),
         extern_mods: visitor.extern_mods,
-    })
+        extern_mod_paths: visitor.extern_mod_paths,
+        keep_imports: filter(visitor.keep_imports),
+        reexports: filter(visitor.reexports),
+        test_fns: visitor.test_fns,
+        declared_imports: filter(visitor.declared_imports),
+        extern_crate_imports: filter(visitor.extern_crate_imports),
+        use_imports: filter(visitor.use_imports),
+        ungated_imports: filter(visitor.ungated_imports),
+        feature_conditions: visitor
+            .feature_conditions
+            .into_iter()
+            .map(|(condition, imports)| (condition, filter(imports)))
+            .collect(),
+        test_mods,
+        import_order: visitor.import_order,
+        referenced_features: visitor.referenced_features.into_iter().collect(),
+        std_externs: visitor.std_externs.into_iter().collect(),
+        macros_skipped: visitor.macros_skipped,
+        diagnostics: visitor.diagnostics,
+        public_api: visitor.public_api.into_iter().collect(),
+        native_links: visitor.native_links,
+        cfg_tree,
+    }
 }
 
-fn filter_imports(imports: HashSet<Ident>) -> Vec<String> {
+fn filter_imports(imports: IdentSet, normalize_case: bool) -> IdentSet {
     imports
         .into_iter()
-        .filter_map(|ident| {
-            let s = ident.to_string();
-            // uppercase is structs
-            // TODO: don't store all the structs! seems wasteful
-            if s.chars().next().map(|c| c.is_lowercase()).unwrap_or(false) {
-                Some(s)
+        .map(|ident| {
+            if normalize_case {
+                lowercase_first(ident)
             } else {
-                None
+                ident
             }
         })
+        .filter(|ident| {
+            // uppercase is structs
+            // TODO: don't store all the structs! seems wasteful
+            ident
+                .to_string()
+                .chars()
+                .next()
+                .map(|c| c.is_lowercase())
+                .unwrap_or(false)
+        })
         .collect()
 }
 
+fn lowercase_first(ident: Ident) -> Ident {
+    let name = ident.to_string();
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_uppercase() => {
+            let lowercased: String = first.to_lowercase().chain(chars).collect();
+            syn::Ident::new(&lowercased, ident.as_ident().span()).into()
+        }
+        _ => ident,
+    }
+}
+
+fn to_strings(imports: IdentSet) -> Vec<String> {
+    imports.iter().map(Ident::to_string).collect()
+}
+
+/// Set of identifiers found while visiting. With the `deterministic` crate feature, this is ordered
+/// so that iterating over it, e.g. when dumping the visitor state while debugging, is reproducible.
+#[cfg(not(feature = "deterministic"))]
+pub type IdentSet<'ast> = HashSet<Ident<'ast>>;
+#[cfg(feature = "deterministic")]
+pub type IdentSet<'ast> = BTreeSet<Ident<'ast>>;
+
 // Macros aren't parsed as part of the overall AST, so when we parse them we get an owned value.
 // This approach allows us to store both the references and the owned values together, minimzing
-// clones.
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
-enum Ident<'ast> {
+// clones. Both variants compare and hash by name, so a crate found in a macro and in the AST is
+// only counted once.
+#[derive(Debug, Clone)]
+pub enum Ident<'ast> {
     Ref(&'ast syn::Ident),
     Owned(syn::Ident),
 }
 
+impl<'ast> PartialEq for Ident<'ast> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ident() == other.as_ident()
+    }
+}
+
+impl<'ast> Eq for Ident<'ast> {}
+
+impl<'ast> PartialOrd for Ident<'ast> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'ast> Ord for Ident<'ast> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_ident().cmp(other.as_ident())
+    }
+}
+
+impl<'ast> std::hash::Hash for Ident<'ast> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_ident().hash(state);
+    }
+}
+
 impl<'ast> From<&'ast syn::Ident> for Ident<'ast> {
     fn from(ident: &'ast syn::Ident) -> Self {
         Self::Ref(ident)
@@ -99,6 +642,13 @@ impl<'ast> PartialEq<&str> for Ident<'ast> {
 }
 
 impl<'ast> Ident<'ast> {
+    pub fn as_ident(&self) -> &syn::Ident {
+        match self {
+            Self::Ref(ident) => ident,
+            Self::Owned(ident) => ident,
+        }
+    }
+
     // NOTE: this is just matching the wrapping the implementation in syn::Ident
     #[allow(clippy::inherent_to_string)]
     fn to_string(&self) -> String {
@@ -109,45 +659,222 @@ impl<'ast> Ident<'ast> {
     }
 }
 
+/// Configurations whose imports are reported separately from regular imports.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Bucket {
+    #[default]
+    Regular,
+    /// behind #[test] or #[cfg(test)]
+    Test,
+    /// behind #[cfg(coverage)]
+    Coverage,
+    /// behind #[cfg(miri)]
+    Miri,
+}
+
+impl Bucket {
+    fn name(self) -> &'static str {
+        match self {
+            Bucket::Regular => "regular",
+            Bucket::Test => "test",
+            Bucket::Coverage => "coverage",
+            Bucket::Miri => "miri",
+        }
+    }
+
+    /// The bucket of a scope with its own cfg nested inside a scope in this bucket.
+    fn nest(self, inner: Option<Bucket>) -> Bucket {
+        match (self, inner) {
+            // test-only code is never built outside of tests, whatever else it is gated on
+            (Bucket::Test, _) => Bucket::Test,
+            (_, Some(inner)) => inner,
+            (outer, None) => outer,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 struct Scope<'ast> {
     /// mods in scope
     mods: Vec<Ident<'ast>>,
-    /// whether this scope is behind #[test] or #[cfg(test)]
-    is_test_only: bool,
+    /// which configuration this scope is compiled in
+    bucket: Bucket,
+    /// the #[cfg(feature = "...")] and #[cfg(not(feature = "..."))] gates this scope is behind,
+    /// including those of the enclosing scopes, which all have to hold
+    features: Vec<FeatureGate>,
+    /// whether this scope opened a cfg scope, if requested
+    opens_cfg_scope: bool,
+    /// whether the uses of the enclosing mod bring a `test` attribute into scope, e.g.
+    /// `use tokio::test;`
+    uses_test_attr: bool,
     /// whether this scope is behind #[gazelle::ignore]
     // TODO: this is not currently used, but we could support #[gazelle::ignore] on things like
     // functions and blocks in the future
     is_ignored: bool,
 }
 
+/// A CfgScope whose imports are still being collected.
+#[derive(Debug)]
+struct OpenCfgScope<'ast> {
+    scope: CfgScope,
+    imports: IdentSet<'ast>,
+}
+
+impl<'ast> OpenCfgScope<'ast> {
+    fn new(predicate: Option<String>, bucket: Bucket, features: &[FeatureGate]) -> Self {
+        Self {
+            scope: CfgScope {
+                predicate,
+                bucket: bucket.name(),
+                condition: FeatureGate::conjunction(features).unwrap_or_default(),
+                imports: Vec::new(),
+                children: Vec::new(),
+            },
+            imports: IdentSet::default(),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct AstVisitor<'ast> {
+    // owned so that the config doesn't have to outlive the AST
+    config: ParseConfig,
     /// crates that are imported
-    imports: HashSet<Ident<'ast>>,
+    imports: IdentSet<'ast>,
     /// crates that are imported in test-only configurations
-    test_imports: HashSet<Ident<'ast>>,
+    test_imports: IdentSet<'ast>,
+    /// crates that are imported in coverage-only configurations
+    coverage_imports: IdentSet<'ast>,
+    /// crates that are imported in miri-only configurations
+    miri_imports: IdentSet<'ast>,
+    /// crates that are imported behind #[cfg(feature = "...")], by feature
+    feature_imports: HashMap<String, IdentSet<'ast>>,
+    /// crates that are imported behind #[cfg(not(feature = "..."))], by feature
+    anti_feature_imports: HashMap<String, IdentSet<'ast>>,
     /// stack of mods in scope
     mod_stack: VecDeque<Scope<'ast>>,
     /// all mods that are currently in scope (including parent scopes)
-    scope_mods: HashSet<Ident<'ast>>,
+    scope_mods: IdentSet<'ast>,
     /// collected hints
     hints: Hints,
     /// bare mods defined in external files
     extern_mods: Vec<String>,
+    /// #[path = "..."] attributes of bare mods
+    extern_mod_paths: HashMap<String, String>,
+    /// path of the mods enclosing the current scope
+    mod_path: Vec<String>,
+    /// paths of all mods defined in this file, inline or external
+    mod_paths: HashSet<String>,
+    /// crates imported by uses under #[allow(unused_imports)]
+    keep_imports: IdentSet<'ast>,
+    /// whether the whole file is under #![allow(unused_imports)]
+    file_allows_unused_imports: bool,
+    /// whether the use currently being visited is under #[allow(unused_imports)]
+    in_kept_use: bool,
+    /// crates re-exported with `pub use`
+    reexports: IdentSet<'ast>,
+    /// whether the use currently being visited is a `pub use`
+    in_reexport: bool,
+    /// #[test] functions, if requested
+    test_fns: Vec<TestFn>,
+    /// crates imported by a `use` or `extern crate`
+    declared_imports: IdentSet<'ast>,
+    /// crates imported by an `extern crate`
+    extern_crate_imports: IdentSet<'ast>,
+    /// crates imported by a `use`
+    use_imports: IdentSet<'ast>,
+    /// crates imported at least once outside a feature cfg
+    ungated_imports: IdentSet<'ast>,
+    /// crates imported under feature cfgs, by the predicate of the feature gates of the scope
+    feature_conditions: HashMap<String, IdentSet<'ast>>,
+    /// whether the item currently being visited is a `use` or `extern crate`
+    in_use_decl: bool,
+    /// whether the item currently being visited is an `extern crate`
+    in_extern_crate: bool,
+    /// paths of the mods containing tests or benchmarks, if requested
+    test_mods: HashSet<String>,
+    /// every import in the order it was first found, if requested
+    import_order: Vec<String>,
+    /// the imports in import_order, to skip the ones that have already been seen
+    seen_imports: HashSet<String>,
+    /// features referred to by cfgs, if requested
+    referenced_features: BTreeSet<String>,
+    /// `extern crate` declarations of the standard library crates, only kept for #![no_std] files
+    std_externs: BTreeSet<String>,
+    /// macros that were too big to scan
+    macros_skipped: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+    /// public items, if requested
+    public_api: BTreeSet<String>,
+    /// libraries linked by extern blocks
+    native_links: Vec<NativeLink>,
+    /// the cfg scopes being visited, outermost first, if requested
+    open_cfg_scopes: Vec<OpenCfgScope<'ast>>,
+    /// number of enclosing private mods and blocks, whose items aren't reachable from outside
+    private_depth: usize,
+    /// full paths of the names brought into the top-level scope by a `use`, e.g. `main` ->
+    /// `tokio::main` for `use tokio::main;`
+    root_use_paths: HashMap<String, String>,
+    /// names brought into scope by a `use` anywhere in the file
+    use_names: HashSet<String>,
+    /// whether there is a `use` glob or #[macro_use] extern crate, which can bring in derives
+    /// without naming them
+    has_unnamed_uses: bool,
+    /// bare derive names like the `Serialize` in #[derive(Serialize)], other than the builtin ones
+    bare_derives: Vec<syn::Ident>,
 }
 
-impl<'ast> Default for AstVisitor<'ast> {
-    fn default() -> Self {
+impl<'ast> AstVisitor<'ast> {
+    fn new(config: &ParseConfig) -> Self {
         let mut mod_stack = VecDeque::new();
         mod_stack.push_back(Scope::default());
         Self {
-            imports: HashSet::default(),
-            test_imports: HashSet::default(),
+            config: config.clone(),
+            imports: IdentSet::default(),
+            test_imports: IdentSet::default(),
+            coverage_imports: IdentSet::default(),
+            miri_imports: IdentSet::default(),
+            feature_imports: HashMap::default(),
+            anti_feature_imports: HashMap::default(),
             mod_stack,
-            scope_mods: HashSet::default(),
+            scope_mods: IdentSet::default(),
             hints: Hints::default(),
             extern_mods: Vec::default(),
+            extern_mod_paths: HashMap::default(),
+            mod_path: Vec::default(),
+            mod_paths: HashSet::default(),
+            keep_imports: IdentSet::default(),
+            file_allows_unused_imports: false,
+            in_kept_use: false,
+            reexports: IdentSet::default(),
+            in_reexport: false,
+            test_fns: Vec::default(),
+            declared_imports: IdentSet::default(),
+            extern_crate_imports: IdentSet::default(),
+            use_imports: IdentSet::default(),
+            ungated_imports: IdentSet::default(),
+            feature_conditions: HashMap::default(),
+            in_use_decl: false,
+            in_extern_crate: false,
+            test_mods: HashSet::default(),
+            import_order: Vec::default(),
+            seen_imports: HashSet::default(),
+            referenced_features: BTreeSet::default(),
+            std_externs: BTreeSet::default(),
+            macros_skipped: Vec::default(),
+            diagnostics: Vec::default(),
+            public_api: BTreeSet::default(),
+            private_depth: 0,
+            native_links: Vec::default(),
+            open_cfg_scopes: if config.emit_cfg_tree {
+                vec![OpenCfgScope::new(None, Bucket::Regular, &[])]
+            } else {
+                Vec::new()
+            },
+            root_use_paths: HashMap::default(),
+            use_names: HashSet::default(),
+            has_unnamed_uses: false,
+            bare_derives: Vec::default(),
         }
     }
 }
@@ -194,6 +921,394 @@ impl DirectiveSet {
     }
 }
 
+/// The bucket selected by #[cfg(test)], #[cfg(coverage)] or #[cfg(miri)], if the attributes include
+/// one of them.
+// NOTE: Any other cfg, including platform cfgs like #[cfg(any(unix, windows))], is treated as always
+// enabled, so imports behind it are regular imports. This is right for cfgs that hold on every
+// supported platform, but over-approximates the deps for a single platform.
+fn parse_cfg_bucket(attrs: &[syn::Attribute]) -> Option<Bucket> {
+    for attr in attrs {
+        if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
+            if let Some(ident) = list.path.get_ident() {
+                if ident == "cfg" && list.nested.len() == 1 {
+                    if let syn::NestedMeta::Meta(syn::Meta::Path(path)) = &list.nested[0] {
+                        if let Some(ident) = path.get_ident() {
+                            if ident == "test" {
+                                return Some(Bucket::Test);
+                            } else if ident == "coverage" {
+                                return Some(Bucket::Coverage);
+                            } else if ident == "miri" {
+                                return Some(Bucket::Miri);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FeatureGate {
+    /// #[cfg(feature = "...")]
+    Enabled(String),
+    /// #[cfg(not(feature = "..."))]
+    Disabled(String),
+}
+
+impl FeatureGate {
+    /// The cfg predicate, e.g. `feature = "x"`.
+    fn condition(&self) -> String {
+        match self {
+            FeatureGate::Enabled(feature) => format!("feature = {:?}", feature),
+            FeatureGate::Disabled(feature) => format!("not(feature = {:?})", feature),
+        }
+    }
+
+    /// The cfg predicate that all of the gates hold, e.g. `all(feature = "x", feature = "y")`, or
+    /// None if there are none.
+    fn conjunction(gates: &[FeatureGate]) -> Option<String> {
+        let conditions: Vec<String> = gates.iter().map(FeatureGate::condition).collect();
+        match conditions.len() {
+            0 => None,
+            1 => conditions.into_iter().next(),
+            _ => Some(format!("all({})", conditions.join(", "))),
+        }
+    }
+}
+
+/// The feature gates that #[cfg(...)] attributes require to hold: every `feature = "..."` and
+/// `not(feature = "...")`, either on its own or within `all(...)`. Other predicates like `unix` are
+/// left out, as are features within `any(...)`, since those aren't required.
+fn parse_cfg_features(attrs: &[syn::Attribute]) -> Vec<FeatureGate> {
+    let mut gates = Vec::new();
+    for attr in attrs {
+        if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
+            if list.path.is_ident("cfg") && list.nested.len() == 1 {
+                if let syn::NestedMeta::Meta(meta) = &list.nested[0] {
+                    collect_feature_gates(meta, &mut gates);
+                }
+            }
+        }
+    }
+    gates
+}
+
+fn collect_feature_gates(meta: &syn::Meta, gates: &mut Vec<FeatureGate>) {
+    if let Some(feature) = parse_feature_predicate(meta) {
+        gates.push(FeatureGate::Enabled(feature));
+    } else if let syn::Meta::List(list) = meta {
+        if list.path.is_ident("not") && list.nested.len() == 1 {
+            if let syn::NestedMeta::Meta(meta) = &list.nested[0] {
+                if let Some(feature) = parse_feature_predicate(meta) {
+                    gates.push(FeatureGate::Disabled(feature));
+                }
+            }
+        } else if list.path.is_ident("all") {
+            for nested in &list.nested {
+                if let syn::NestedMeta::Meta(meta) = nested {
+                    collect_feature_gates(meta, gates);
+                }
+            }
+        }
+    }
+}
+
+/// The feature gates of a scope within a scope behind `outer`, which are required as well.
+fn nest_features(outer: &[FeatureGate], inner: Vec<FeatureGate>) -> Vec<FeatureGate> {
+    let mut features = outer.to_vec();
+    for gate in inner {
+        if !features.contains(&gate) {
+            features.push(gate);
+        }
+    }
+    features
+}
+
+/// Adds the features of every `feature = "..."` in a cfg predicate, however deeply it is nested.
+fn collect_features(meta: &syn::Meta, features: &mut BTreeSet<String>) {
+    if let Some(feature) = parse_feature_predicate(meta) {
+        features.insert(feature);
+    } else if let syn::Meta::List(list) = meta {
+        for nested in &list.nested {
+            if let syn::NestedMeta::Meta(meta) = nested {
+                collect_features(meta, features);
+            }
+        }
+    }
+}
+
+/// The feature name of a `feature = "..."` cfg predicate.
+fn parse_feature_predicate(meta: &syn::Meta) -> Option<String> {
+    if let syn::Meta::NameValue(name_value) = meta {
+        if name_value.path.is_ident("feature") {
+            if let syn::Lit::Str(feature) = &name_value.lit {
+                return Some(feature.value());
+            }
+        }
+    }
+    None
+}
+
+/// The cfgs on an item that change how its imports are reported.
+#[derive(Debug, Default)]
+struct Cfg {
+    bucket: Option<Bucket>,
+    features: Vec<FeatureGate>,
+    /// the whole predicate, e.g. `all(unix, feature = "x")`, for ParseConfig::emit_cfg_tree
+    predicate: Option<String>,
+}
+
+impl Cfg {
+    fn parse(attrs: &[syn::Attribute]) -> Self {
+        Self {
+            bucket: parse_cfg_bucket(attrs),
+            features: parse_cfg_features(attrs),
+            predicate: parse_cfg_predicate(attrs),
+        }
+    }
+}
+
+/// The predicate of the #[cfg(...)] attributes, combined with `all(...)` if there is more than one.
+fn parse_cfg_predicate(attrs: &[syn::Attribute]) -> Option<String> {
+    let mut predicates = Vec::new();
+    for attr in attrs {
+        if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
+            if list.path.is_ident("cfg") && list.nested.len() == 1 {
+                predicates.push(format_cfg_predicate(&list.nested[0]));
+            }
+        }
+    }
+    match predicates.len() {
+        0 => None,
+        1 => predicates.pop(),
+        _ => Some(format!("all({})", predicates.join(", "))),
+    }
+}
+
+/// Formats a cfg predicate the way it's usually written, e.g. `not(feature = "x")`.
+fn format_cfg_predicate(nested: &syn::NestedMeta) -> String {
+    let format_path = |path: &syn::Path| {
+        let segments: Vec<String> = path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect();
+        segments.join("::")
+    };
+    let format_lit = |lit: &syn::Lit| match lit {
+        syn::Lit::Str(lit) => format!("{:?}", lit.value()),
+        syn::Lit::Bool(lit) => lit.value.to_string(),
+        syn::Lit::Int(lit) => lit.base10_digits().to_string(),
+        _ => "_".to_string(),
+    };
+    match nested {
+        syn::NestedMeta::Meta(syn::Meta::Path(path)) => format_path(path),
+        syn::NestedMeta::Meta(syn::Meta::NameValue(name_value)) => format!(
+            "{} = {}",
+            format_path(&name_value.path),
+            format_lit(&name_value.lit)
+        ),
+        syn::NestedMeta::Meta(syn::Meta::List(list)) => {
+            let nested: Vec<String> = list.nested.iter().map(format_cfg_predicate).collect();
+            format!("{}({})", format_path(&list.path), nested.join(", "))
+        }
+        syn::NestedMeta::Lit(lit) => format_lit(lit),
+    }
+}
+
+/// The outer attributes of an expression, e.g. the #[cfg(...)] in `#[cfg(...)] f();`.
+fn expr_attrs(expr: &syn::Expr) -> &[syn::Attribute] {
+    match expr {
+        syn::Expr::Array(expr) => &expr.attrs,
+        syn::Expr::Assign(expr) => &expr.attrs,
+        syn::Expr::AssignOp(expr) => &expr.attrs,
+        syn::Expr::Async(expr) => &expr.attrs,
+        syn::Expr::Await(expr) => &expr.attrs,
+        syn::Expr::Binary(expr) => &expr.attrs,
+        syn::Expr::Block(expr) => &expr.attrs,
+        syn::Expr::Box(expr) => &expr.attrs,
+        syn::Expr::Break(expr) => &expr.attrs,
+        syn::Expr::Call(expr) => &expr.attrs,
+        syn::Expr::Cast(expr) => &expr.attrs,
+        syn::Expr::Closure(expr) => &expr.attrs,
+        syn::Expr::Continue(expr) => &expr.attrs,
+        syn::Expr::Field(expr) => &expr.attrs,
+        syn::Expr::ForLoop(expr) => &expr.attrs,
+        syn::Expr::Group(expr) => &expr.attrs,
+        syn::Expr::If(expr) => &expr.attrs,
+        syn::Expr::Index(expr) => &expr.attrs,
+        syn::Expr::Let(expr) => &expr.attrs,
+        syn::Expr::Lit(expr) => &expr.attrs,
+        syn::Expr::Loop(expr) => &expr.attrs,
+        syn::Expr::Macro(expr) => &expr.attrs,
+        syn::Expr::Match(expr) => &expr.attrs,
+        syn::Expr::MethodCall(expr) => &expr.attrs,
+        syn::Expr::Paren(expr) => &expr.attrs,
+        syn::Expr::Path(expr) => &expr.attrs,
+        syn::Expr::Range(expr) => &expr.attrs,
+        syn::Expr::Reference(expr) => &expr.attrs,
+        syn::Expr::Repeat(expr) => &expr.attrs,
+        syn::Expr::Return(expr) => &expr.attrs,
+        syn::Expr::Struct(expr) => &expr.attrs,
+        syn::Expr::Try(expr) => &expr.attrs,
+        syn::Expr::TryBlock(expr) => &expr.attrs,
+        syn::Expr::Tuple(expr) => &expr.attrs,
+        syn::Expr::Type(expr) => &expr.attrs,
+        syn::Expr::Unary(expr) => &expr.attrs,
+        syn::Expr::Unsafe(expr) => &expr.attrs,
+        syn::Expr::While(expr) => &expr.attrs,
+        syn::Expr::Yield(expr) => &expr.attrs,
+        _ => &[],
+    }
+}
+
+/// The names of the libraries in #[link(name = "...")] attributes.
+fn parse_link_names(attrs: &[syn::Attribute]) -> Vec<String> {
+    let mut names = Vec::new();
+    for attr in attrs {
+        if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
+            if list.path.is_ident("link") {
+                for nested in &list.nested {
+                    if let syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                        path,
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    })) = nested
+                    {
+                        if path.is_ident("name") {
+                            names.push(lit.value());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    names
+}
+
+/// The value of #[path = "..."], if present.
+fn parse_path_attr(attrs: &[syn::Attribute]) -> Option<String> {
+    for attr in attrs {
+        if let Ok(syn::Meta::NameValue(syn::MetaNameValue {
+            path,
+            lit: syn::Lit::Str(lit),
+            ..
+        })) = attr.parse_meta()
+        {
+            if path.is_ident("path") {
+                return Some(lit.value());
+            }
+        }
+    }
+    None
+}
+
+/// Records the full path of each name a use tree brings into scope, e.g. `main` -> `tokio::main`.
+fn collect_use_paths(
+    tree: &syn::UseTree,
+    prefix: &mut Vec<String>,
+    out: &mut HashMap<String, String>,
+) {
+    match tree {
+        syn::UseTree::Path(path) => {
+            prefix.push(path.ident.to_string());
+            collect_use_paths(&path.tree, prefix, out);
+            prefix.pop();
+        }
+        syn::UseTree::Name(name) => {
+            prefix.push(name.ident.to_string());
+            out.insert(name.ident.to_string(), prefix.join("::"));
+            prefix.pop();
+        }
+        syn::UseTree::Rename(rename) => {
+            prefix.push(rename.ident.to_string());
+            out.insert(rename.rename.to_string(), prefix.join("::"));
+            prefix.pop();
+        }
+        syn::UseTree::Group(group) => {
+            for tree in &group.items {
+                collect_use_paths(tree, prefix, out);
+            }
+        }
+        syn::UseTree::Glob(_) => (),
+    }
+}
+
+/// Whether the uses among the items of a mod bring a `test` attribute into scope, e.g.
+/// `use tokio::test;`. A `use super::*;` also brings in the parent's.
+fn uses_test_attr(items: &[syn::Item], parent_uses_test_attr: bool) -> bool {
+    items.iter().any(|item| match item {
+        syn::Item::Use(item_use) => {
+            let mut use_paths = HashMap::new();
+            collect_use_paths(&item_use.tree, &mut Vec::new(), &mut use_paths);
+            let is_super_glob = matches!(
+                &item_use.tree,
+                syn::UseTree::Path(path)
+                    if path.ident == "super" && matches!(*path.tree, syn::UseTree::Glob(_))
+            );
+            use_paths.contains_key("test") || (is_super_glob && parent_uses_test_attr)
+        }
+        _ => false,
+    })
+}
+
+/// Derives that are built into the compiler, so they never come from a crate.
+const BUILTIN_DERIVES: &[&str] = &[
+    "Clone",
+    "Copy",
+    "Debug",
+    "Default",
+    "Eq",
+    "Hash",
+    "Ord",
+    "PartialEq",
+    "PartialOrd",
+];
+
+/// Number of tokens in the stream, including the ones nested in groups.
+fn count_tokens(tokens: &TokenStream) -> usize {
+    tokens
+        .clone()
+        .into_iter()
+        .map(|tree| match tree {
+            TokenTree::Group(group) => 1 + count_tokens(&group.stream()),
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Attributes that are part of the API contract of the item they're on.
+const API_MARKERS: &[&str] = &["non_exhaustive", "stable", "unstable", "deprecated"];
+
+/// Whether the macro builds tokens for code elsewhere, like quote! in a proc macro.
+fn is_quote_macro(ident: &proc_macro2::Ident) -> bool {
+    ident == "quote" || ident == "quote_spanned" || ident == "parse_quote"
+}
+
+fn is_punct(tree: &TokenTree, c: char) -> bool {
+    matches!(tree, TokenTree::Punct(punct) if punct.as_char() == c)
+}
+
+/// Whether the attributes include #[allow(unused_imports)], or #[allow(unused)] which covers it.
+fn allows_unused_imports(attrs: &[syn::Attribute]) -> bool {
+    for attr in attrs {
+        if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
+            if list.path.is_ident("allow") {
+                for nested in &list.nested {
+                    if let syn::NestedMeta::Meta(syn::Meta::Path(path)) = nested {
+                        if path.is_ident("unused_imports") || path.is_ident("unused") {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    false
+}
+
 impl<'ast> AstVisitor<'ast> {
     fn add_import<I: Into<Ident<'ast>>>(&mut self, ident: I) {
         let ident = ident.into();
@@ -203,12 +1318,80 @@ impl<'ast> AstVisitor<'ast> {
             return;
         }
 
+        if self.config.prod_only && self.current_bucket() == Bucket::Test {
+            return;
+        }
+
         if !self.scope_mods.contains(&ident) && !self.is_ignored_scope() {
-            if self.is_test_only_scope() {
-                self.test_imports.insert(ident);
-            } else {
-                self.imports.insert(ident);
+            if self.in_kept_use {
+                self.keep_imports.insert(ident.clone());
+            }
+            if self.in_reexport {
+                self.reexports.insert(ident.clone());
+            }
+            if self.in_use_decl {
+                self.declared_imports.insert(ident.clone());
+                if self.in_extern_crate {
+                    self.extern_crate_imports.insert(ident.clone());
+                } else {
+                    self.use_imports.insert(ident.clone());
+                }
+            }
+            if let Some(open) = self.open_cfg_scopes.last_mut() {
+                open.imports.insert(ident.clone());
+            }
+            if self.config.source_order {
+                // recorded under the name it's reported as, e.g. `zeta` for `extern crate Zeta`
+                let name = if self.config.normalize_case {
+                    lowercase_first(ident.clone()).to_string()
+                } else {
+                    ident.to_string()
+                };
+                if self.seen_imports.insert(name.clone()) {
+                    self.import_order.push(name);
+                }
             }
+            let features = &self.mod_stack.back().unwrap().features;
+            for gate in features {
+                let feature_imports = match gate {
+                    FeatureGate::Enabled(feature) => self.feature_imports.entry(feature.clone()),
+                    FeatureGate::Disabled(feature) => {
+                        self.anti_feature_imports.entry(feature.clone())
+                    }
+                };
+                feature_imports.or_default().insert(ident.clone());
+            }
+            match FeatureGate::conjunction(features) {
+                Some(condition) => {
+                    let imports = self.feature_conditions.entry(condition).or_default();
+                    imports.insert(ident.clone());
+                }
+                None => {
+                    self.ungated_imports.insert(ident.clone());
+                }
+            }
+            match self.current_bucket() {
+                Bucket::Regular => self.imports.insert(ident),
+                Bucket::Test => self.test_imports.insert(ident),
+                Bucket::Coverage => self.coverage_imports.insert(ident),
+                Bucket::Miri => self.miri_imports.insert(ident),
+            };
+        }
+    }
+
+    /// Imports the first path segment of a use, or of each use in a root group like
+    /// `use ::{a, b::c};`.
+    fn add_use_roots(&mut self, tree: &'ast syn::UseTree) {
+        match tree {
+            syn::UseTree::Path(path) => self.add_import(&path.ident),
+            syn::UseTree::Name(name) => self.add_import(&name.ident),
+            syn::UseTree::Rename(rename) => self.add_import(&rename.ident),
+            syn::UseTree::Group(group) => {
+                for tree in &group.items {
+                    self.add_use_roots(tree);
+                }
+            }
+            syn::UseTree::Glob(_) => (),
         }
     }
 
@@ -221,44 +1404,308 @@ impl<'ast> AstVisitor<'ast> {
         }
     }
 
-    fn push_scope(&mut self, test: bool, ignored: bool) {
+    fn push_scope(&mut self, cfg: Cfg, ignored: bool) {
         // TODO: create stack entry lazily so that we avoid it if there are no renames in this scope
         let current_scope = self.mod_stack.back().unwrap();
+        let opens_cfg_scope = self.config.emit_cfg_tree && cfg.predicate.is_some();
         self.mod_stack.push_back(Scope {
             mods: Vec::new(),
-            // scopes within test-only scopes are also test-only
-            is_test_only: test || current_scope.is_test_only,
+            // scopes within e.g. test-only scopes are also test-only
+            bucket: current_scope.bucket.nest(cfg.bucket),
+            features: nest_features(&current_scope.features, cfg.features),
+            opens_cfg_scope,
+            uses_test_attr: current_scope.uses_test_attr,
             is_ignored: ignored || current_scope.is_ignored,
         });
+        if opens_cfg_scope {
+            self.open_cfg_scope(cfg.predicate);
+        }
+    }
+
+    /// Starts a child of the innermost cfg scope, in the bucket and feature of the current scope.
+    fn open_cfg_scope(&mut self, predicate: Option<String>) {
+        let scope = self.mod_stack.back().unwrap();
+        let open = OpenCfgScope::new(predicate, scope.bucket, &scope.features);
+        self.open_cfg_scopes.push(open);
+    }
+
+    /// Finishes the innermost cfg scope and adds it to the children of its parent.
+    fn end_cfg_scope(&mut self) {
+        let open = self
+            .open_cfg_scopes
+            .pop()
+            .expect("hit bottom of cfg scopes");
+        let scope = self.close_cfg_scope(open);
+        let parent = self
+            .open_cfg_scopes
+            .last_mut()
+            .expect("hit bottom of cfg scopes");
+        parent.scope.children.push(scope);
+    }
+
+    fn close_cfg_scope(&self, open: OpenCfgScope<'ast>) -> CfgScope {
+        let mut scope = open.scope;
+        scope.imports = to_strings(filter_imports(open.imports, self.config.normalize_case));
+        scope.imports.sort();
+        scope
+    }
+
+    /// Visits an item, statement or match arm that doesn't introduce a scope of its own, e.g. a use
+    /// or a trait, with its cfgs applied to the current scope just while visiting it. Everything it
+    /// names still stays in scope afterwards.
+    fn visit_with_cfg(&mut self, cfg: Cfg, visit: impl FnOnce(&mut Self)) {
+        let scope = self.mod_stack.back_mut().unwrap();
+        let outer_bucket = scope.bucket;
+        let outer_features = scope.features.clone();
+        scope.bucket = outer_bucket.nest(cfg.bucket);
+        scope.features = nest_features(&outer_features, cfg.features);
+
+        let opens_cfg_scope = self.config.emit_cfg_tree && cfg.predicate.is_some();
+        if opens_cfg_scope {
+            self.open_cfg_scope(cfg.predicate);
+        }
+        visit(self);
+        if opens_cfg_scope {
+            self.end_cfg_scope();
+        }
+
+        let scope = self.mod_stack.back_mut().unwrap();
+        scope.bucket = outer_bucket;
+        scope.features = outer_features;
     }
 
     fn pop_scope(&mut self) {
-        for rename in self.mod_stack.pop_back().expect("hit bottom of stack").mods {
+        let scope = self.mod_stack.pop_back().expect("hit bottom of stack");
+        for rename in scope.mods {
             self.scope_mods.remove(&rename);
         }
+        if scope.opens_cfg_scope {
+            self.end_cfg_scope();
+        }
     }
 
     fn is_root_scope(&self) -> bool {
         self.mod_stack.len() == 1
     }
 
-    fn is_test_only_scope(&self) -> bool {
-        self.mod_stack.back().unwrap().is_test_only
+    fn current_bucket(&self) -> Bucket {
+        self.mod_stack.back().unwrap().bucket
+    }
+
+    /// Whether an item with these cfgs is test-only and shouldn't be visited at all because of
+    /// ParseConfig::prod_only.
+    fn is_skipped_test_scope(&self, cfg: &Cfg) -> bool {
+        self.config.prod_only && self.current_bucket().nest(cfg.bucket) == Bucket::Test
+    }
+
+    /// Records a warning about something that may make the imports incomplete, located at the
+    /// start of the span.
+    fn warn(&mut self, code: &str, message: String, span: Span) {
+        let start = span.start();
+        let mut diagnostic = Diagnostic::default();
+        diagnostic.set_severity("warning".to_string());
+        diagnostic.set_code(code.to_string());
+        diagnostic.set_message(message);
+        diagnostic.set_line(start.line as u32);
+        // proc-macro2 columns are 0-based, unlike rustc's
+        diagnostic.set_column(start.column as u32 + 1);
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Whether the macro is small enough to scan, recording it as skipped if not.
+    fn should_scan_macro(&mut self, name: String, tokens: &TokenStream, span: Span) -> bool {
+        match self.config.max_macro_tokens {
+            Some(max_macro_tokens) if count_tokens(tokens) > max_macro_tokens => {
+                self.warn(
+                    "macro_skipped",
+                    format!(
+                        "macro `{}` has more than {} tokens, so it wasn't scanned for imports",
+                        name, max_macro_tokens
+                    ),
+                    span,
+                );
+                self.macros_skipped.push(name);
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Finds crates referenced by the expansions of a macro_rules! definition. Macro bodies are
+    /// just tokens, so this is a heuristic: the first segment of every path is treated as a crate,
+    /// except for `$crate` and other metavariables.
+    fn scan_macro_rules(&mut self, tokens: &TokenStream) {
+        // the rules are `(matcher) => { transcriber };`, and only the transcribers expand to code
+        let mut after_arrow = false;
+        let mut prev_is_eq = false;
+        for tree in tokens.clone() {
+            match &tree {
+                TokenTree::Group(group) if after_arrow => {
+                    self.scan_macro_transcriber(group.stream());
+                    after_arrow = false;
+                }
+                _ if prev_is_eq && is_punct(&tree, '>') => {
+                    after_arrow = true;
+                }
+                _ => (),
+            }
+            prev_is_eq = is_punct(&tree, '=');
+        }
+    }
+
+    fn scan_macro_transcriber(&mut self, tokens: TokenStream) {
+        let trees: Vec<TokenTree> = tokens.into_iter().collect();
+        let punct_at = |i: usize, c: char| matches!(trees.get(i), Some(tree) if is_punct(tree, c));
+
+        for (i, tree) in trees.iter().enumerate() {
+            match tree {
+                TokenTree::Group(group) => {
+                    // e.g. `quote! { ... }`, which describes code for another crate
+                    let follows_quote = matches!(
+                        &trees[i.saturating_sub(2)],
+                        TokenTree::Ident(ident) if is_quote_macro(ident)
+                    );
+                    let is_quote_body = i >= 2 && punct_at(i - 1, '!') && follows_quote;
+                    if !is_quote_body || self.config.scan_quote_bodies {
+                        self.scan_macro_transcriber(group.stream());
+                    }
+                }
+                TokenTree::Ident(ident) => {
+                    let starts_path = punct_at(i + 1, ':') && punct_at(i + 2, ':');
+                    // e.g. `$crate` or `$module`, which are filled in by the caller, or `#module`
+                    // in a quote! body
+                    let is_metavariable = i >= 1 && (punct_at(i - 1, '$') || punct_at(i - 1, '#'));
+                    // e.g. the `b` in `a::b::c` or `<T as Trait>::b`, but not the `a` in `::a::b`
+                    let continues_path = i >= 3
+                        && punct_at(i - 1, ':')
+                        && punct_at(i - 2, ':')
+                        && (punct_at(i - 3, '>') || matches!(trees[i - 3], TokenTree::Ident(_)));
+                    // e.g. `$e.iter().collect::<Vec<_>>()`, since a crate never takes generics
+                    let is_method = (i >= 1 && punct_at(i - 1, '.')) || punct_at(i + 3, '<');
+
+                    if starts_path && !is_metavariable && !continues_path && !is_method {
+                        self.add_import(ident.clone());
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn scan_asm_operands(&mut self, tokens: &TokenStream) {
+        // e.g. `asm!("call {}", sym foo::bar)`, where `sym` takes a path to a function or static
+        let trees: Vec<TokenTree> = tokens.clone().into_iter().collect();
+        let punct_at = |i: usize, c: char| matches!(trees.get(i), Some(tree) if is_punct(tree, c));
+
+        for (i, tree) in trees.iter().enumerate() {
+            if let TokenTree::Ident(ident) = tree {
+                if ident == "sym" && punct_at(i + 2, ':') && punct_at(i + 3, ':') {
+                    if let Some(TokenTree::Ident(ident)) = trees.get(i + 1) {
+                        self.add_import(ident.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Records attributes like #[non_exhaustive] and #[stable] that are part of the API contract of
+    /// a public item.
+    fn add_api_markers(&mut self, vis: &syn::Visibility, attrs: &[syn::Attribute], name: String) {
+        if self.private_depth > 0 || !matches!(vis, syn::Visibility::Public(_)) {
+            return;
+        }
+        for attr in attrs {
+            if let Some(marker) = API_MARKERS.iter().find(|marker| attr.path.is_ident(marker)) {
+                let mut path = self.mod_path.clone();
+                path.push(name.clone());
+
+                let mut api_marker = ApiMarker::default();
+                api_marker.set_marker(marker.to_string());
+                api_marker.set_item(path.join("::"));
+                self.hints.public_api_markers.push(api_marker);
+            }
+        }
+    }
+
+    fn add_public_item(&mut self, kind: &str, vis: &syn::Visibility, ident: &syn::Ident) {
+        if self.config.emit_public_api
+            && self.private_depth == 0
+            && matches!(vis, syn::Visibility::Public(_))
+        {
+            let mut path = self.mod_path.clone();
+            path.push(ident.to_string());
+            self.public_api
+                .insert(format!("{} {}", kind, path.join("::")));
+        }
+    }
+
+    fn add_test_fn(&mut self, node: &syn::ItemFn) {
+        let mut test_fn = TestFn::default();
+        // the same path that libtest reports, e.g. `tests::it_works`
+        let mut name = self.mod_path.clone();
+        name.push(node.sig.ident.to_string());
+        test_fn.set_name(name.join("::"));
+
+        let modifiers = test_fn.mut_modifiers();
+        for attr in &node.attrs {
+            // these can have arguments, e.g. #[should_panic(expected = "...")] or #[ignore = "..."]
+            if attr.path.is_ident("should_panic") {
+                modifiers.push("should_panic".to_string());
+            } else if attr.path.is_ident("ignore") {
+                modifiers.push("ignore".to_string());
+            }
+        }
+        if node.sig.asyncness.is_some() {
+            modifiers.push("async".to_string());
+        }
+
+        self.test_fns.push(test_fn);
     }
 
     fn is_ignored_scope(&self) -> bool {
         self.mod_stack.back().unwrap().is_ignored
     }
 
+    fn is_entry_attr(&self, attr: &syn::Attribute) -> bool {
+        let path = attr
+            .path
+            .segments
+            .iter()
+            .map(|seg| seg.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::");
+        // best-effort: a bare attribute may be an entry attribute brought in with a use, e.g.
+        // #[main] after `use tokio::main;`
+        let path = match self.root_use_paths.get(&path) {
+            Some(full_path) if attr.path.segments.len() == 1 => full_path.clone(),
+            _ => path,
+        };
+        self.config.entry_attrs.contains(&path)
+    }
+
     fn visit_type_attrs(&mut self, attrs: &'ast Vec<syn::Attribute>) {
-        // parse #[derive(A, B, ...)]
+        // parse #[derive(A, B, ...)] and #[repr(...)]
         for attr in attrs {
             if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
                 if let Some(ident) = list.path.get_ident() {
-                    if ident == "derive" {
+                    if ident == "repr" {
+                        // #[repr(C)] and #[repr(transparent)] usually mean the type crosses an FFI
+                        // boundary
+                        for nested in &list.nested {
+                            if let syn::NestedMeta::Meta(syn::Meta::Path(path)) = nested {
+                                if path.is_ident("C") || path.is_ident("transparent") {
+                                    self.hints.has_repr_c = true;
+                                }
+                            }
+                        }
+                    } else if ident == "derive" {
                         for nested in list.nested {
                             if let syn::NestedMeta::Meta(syn::Meta::Path(path)) = nested {
-                                if path.segments.len() > 1 {
+                                if let Some(ident) = path.get_ident() {
+                                    if !BUILTIN_DERIVES.iter().any(|builtin| ident == builtin) {
+                                        self.bare_derives.push(ident.clone());
+                                    }
+                                } else {
                                     // this dance moves it out to avoid a clone
                                     self.add_import(
                                         path.segments
@@ -297,15 +1744,153 @@ impl<'ast> AstVisitor<'ast> {
 }
 
 impl<'ast> Visit<'ast> for AstVisitor<'ast> {
+    fn visit_file(&mut self, node: &'ast syn::File) {
+        // parse crate-level attributes like #![recursion_limit = "256"] and #![deny(warnings)]
+        for attr in &node.attrs {
+            match attr.parse_meta() {
+                Ok(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit),
+                    ..
+                })) => {
+                    if let Ok(limit) = lit.value().parse::<u32>() {
+                        if path.is_ident("recursion_limit") {
+                            self.hints.recursion_limit = limit;
+                        } else if path.is_ident("type_length_limit") {
+                            self.hints.type_length_limit = limit;
+                        }
+                    }
+                }
+                Ok(syn::Meta::Path(path)) if path.is_ident("no_std") => {
+                    self.hints.is_no_std = true;
+                }
+                Ok(syn::Meta::List(list)) => {
+                    let level = match list.path.get_ident() {
+                        Some(ident)
+                            if ident == "allow"
+                                || ident == "warn"
+                                || ident == "deny"
+                                || ident == "forbid" =>
+                        {
+                            ident.to_string()
+                        }
+                        _ => continue,
+                    };
+                    for nested in &list.nested {
+                        if let syn::NestedMeta::Meta(syn::Meta::Path(path)) = nested {
+                            let mut lint_level = LintLevel::default();
+                            lint_level.set_level(level.clone());
+                            // lints can be namespaced by tool, e.g. clippy::all
+                            lint_level.set_lint(
+                                path.segments
+                                    .iter()
+                                    .map(|seg| seg.ident.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join("::"),
+                            );
+                            self.hints.crate_lint_levels.push(lint_level);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        self.file_allows_unused_imports = allows_unused_imports(&node.attrs);
+        // uses can come after the functions that rely on them, so collect them up front
+        for item in &node.items {
+            if let syn::Item::Use(item_use) = item {
+                collect_use_paths(&item_use.tree, &mut Vec::new(), &mut self.root_use_paths);
+            }
+        }
+        self.mod_stack.front_mut().unwrap().uses_test_attr =
+            self.root_use_paths.contains_key("test");
+
+        visit::visit_file(self, node);
+
+        // the standard library crates are implicitly available unless the file is #![no_std], so
+        // only declaring them there is meaningful
+        if !self.hints.is_no_std {
+            self.std_externs.clear();
+        }
+
+        // a bare derive that wasn't brought in by a use comes from a crate we can't see
+        if !self.has_unnamed_uses {
+            let bare_derives = std::mem::take(&mut self.bare_derives);
+            for derive in bare_derives {
+                if !self.use_names.contains(&derive.to_string()) {
+                    self.hints.has_unresolvable_derive = true;
+                    self.warn(
+                        "unresolvable_derive",
+                        format!("can't tell which crate #[derive({})] comes from", derive),
+                        derive.span(),
+                    );
+                }
+            }
+        }
+    }
+
+    fn visit_attribute(&mut self, node: &'ast syn::Attribute) {
+        // #[coverage(off)] excludes an item from coverage instrumentation
+        if let Ok(syn::Meta::List(list)) = node.parse_meta() {
+            if list.path.is_ident("coverage") && list.nested.len() == 1 {
+                if let syn::NestedMeta::Meta(syn::Meta::Path(path)) = &list.nested[0] {
+                    if path.is_ident("off") {
+                        self.hints.has_coverage_off = true;
+                    }
+                }
+            }
+        }
+        // #[cfg(...)] and #[cfg_attr(..., ...)]
+        if self.config.list_features
+            && (node.path.is_ident("cfg") || node.path.is_ident("cfg_attr"))
+        {
+            if let Ok(meta) = node.parse_meta() {
+                collect_features(&meta, &mut self.referenced_features);
+            }
+        }
+        visit::visit_attribute(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        // cfg!(...)
+        if self.config.list_features && node.path.is_ident("cfg") {
+            if let Ok(meta) = node.parse_body::<syn::Meta>() {
+                collect_features(&meta, &mut self.referenced_features);
+            }
+        }
+        // asm!(...) and global_asm!(...); best-effort since the operands are only loosely parsed
+        if let Some(last) = node.path.segments.last() {
+            if (last.ident == "asm" || last.ident == "global_asm")
+                && self.should_scan_macro(last.ident.to_string(), &node.tokens, node.path.span())
+            {
+                self.scan_asm_operands(&node.tokens);
+            }
+            if self.config.scan_quote_bodies
+                && is_quote_macro(&last.ident)
+                && self.should_scan_macro(last.ident.to_string(), &node.tokens, node.path.span())
+            {
+                self.scan_macro_transcriber(node.tokens.clone());
+            }
+        }
+        visit::visit_macro(self, node);
+    }
+
     fn visit_use_name(&mut self, node: &'ast syn::UseName) {
+        self.use_names.insert(node.ident.to_string());
         self.add_mod(&node.ident);
     }
 
     fn visit_use_rename(&mut self, node: &'ast syn::UseRename) {
-        self.add_import(&node.ident);
+        // the original name is only an import at the root, which visit_item_use handles
+        self.use_names.insert(node.rename.to_string());
         self.add_mod(&node.rename);
     }
 
+    fn visit_use_glob(&mut self, node: &'ast syn::UseGlob) {
+        self.has_unnamed_uses = true;
+        visit::visit_use_glob(self, node);
+    }
+
     fn visit_path(&mut self, node: &'ast syn::Path) {
         if node.segments.len() > 1 {
             self.add_import(&node.segments[0].ident);
@@ -316,19 +1901,31 @@ impl<'ast> Visit<'ast> for AstVisitor<'ast> {
     fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
         let directives = self.parse_directives(&node.attrs);
 
-        // NOTE: We want to ignore any dependencies inside the ignored scope. However, we still want
-        // to bring anything imported into scope, hence the visit::visit_item_use outside the
-        // conditional below.
-        if !directives.should_ignore() {
-            // the first path segment is an import
-            match &node.tree {
-                syn::UseTree::Path(path) => self.add_import(&path.ident),
-                syn::UseTree::Name(name) => self.add_import(&name.ident),
-                _ => (),
+        // the author knows this use looks unused (e.g. it brings a trait into scope), so the crates
+        // it imports shouldn't be pruned
+        self.in_kept_use = self.file_allows_unused_imports || allows_unused_imports(&node.attrs);
+
+        // only a fully public use re-exports the crate to dependents; pub(crate), pub(super), and
+        // pub(in path) uses are internal
+        self.in_reexport = matches!(node.vis, syn::Visibility::Public(_));
+
+        self.in_use_decl = true;
+
+        // e.g. a #[cfg(test)] use only imports crates for tests
+        self.visit_with_cfg(Cfg::parse(&node.attrs), |this| {
+            // NOTE: We want to ignore any dependencies inside the ignored scope. However, we still
+            // want to bring anything imported into scope, hence the visit::visit_item_use outside
+            // the conditional below.
+            if !directives.should_ignore() {
+                this.add_use_roots(&node.tree);
             }
-        }
 
-        visit::visit_item_use(self, node);
+            visit::visit_item_use(this, node);
+        });
+
+        self.in_kept_use = false;
+        self.in_reexport = false;
+        self.in_use_decl = false;
     }
 
     fn visit_use_path(&mut self, node: &'ast syn::UsePath) {
@@ -348,86 +1945,254 @@ impl<'ast> Visit<'ast> for AstVisitor<'ast> {
 
     fn visit_item_extern_crate(&mut self, node: &'ast syn::ItemExternCrate) {
         let directives = self.parse_directives(&node.attrs);
+        if node
+            .attrs
+            .iter()
+            .any(|attr| attr.path.is_ident("macro_use"))
+        {
+            self.has_unnamed_uses = true;
+        }
         if !directives.should_ignore() {
+            self.in_use_decl = true;
+            self.in_extern_crate = true;
             self.add_import(&node.ident);
+            self.in_use_decl = false;
+            self.in_extern_crate = false;
+            if node.ident == "alloc" || node.ident == "core" || node.ident == "std" {
+                self.std_externs.insert(node.ident.to_string());
+            }
         }
     }
 
+    fn visit_stmt(&mut self, node: &'ast syn::Stmt) {
+        // e.g. `#[cfg(feature = "x")] dep::f();`; items apply their own cfgs
+        let attrs = match node {
+            syn::Stmt::Local(local) => &local.attrs[..],
+            syn::Stmt::Expr(expr) | syn::Stmt::Semi(expr, _) => expr_attrs(expr),
+            syn::Stmt::Item(_) => &[],
+        };
+        self.visit_with_cfg(Cfg::parse(attrs), |this| {
+            visit::visit_stmt(this, node);
+        });
+    }
+
+    fn visit_arm(&mut self, node: &'ast syn::Arm) {
+        // e.g. `#[cfg(feature = "x")] Kind::X => dep::f(),`
+        self.visit_with_cfg(Cfg::parse(&node.attrs), |this| {
+            visit::visit_arm(this, node);
+        });
+    }
+
     fn visit_block(&mut self, node: &'ast syn::Block) {
-        self.push_scope(false, false);
+        self.push_scope(Cfg::default(), false);
+        self.private_depth += 1;
         visit::visit_block(self, node);
+        self.private_depth -= 1;
         self.pop_scope();
     }
 
     fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
-        let mut is_test_only = false;
-
-        // parse #[cfg(test)]
-        for attr in &node.attrs {
-            if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
-                if let Some(ident) = list.path.get_ident() {
-                    if ident == "cfg" && list.nested.len() == 1 {
-                        if let syn::NestedMeta::Meta(syn::Meta::Path(path)) = &list.nested[0] {
-                            if let Some(ident) = path.get_ident() {
-                                if ident == "test" {
-                                    is_test_only = true;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+        let cfg = Cfg::parse(&node.attrs);
+        self.add_mod(&node.ident);
+        if self.is_skipped_test_scope(&cfg) {
+            return;
         }
 
         if self.is_root_scope() && node.content.is_none() {
             // this mod is defined in a different file
             self.extern_mods.push(node.ident.to_string());
+
+            // the file might not be in the usual place
+            if let Some(path) = parse_path_attr(&node.attrs) {
+                self.extern_mod_paths.insert(node.ident.to_string(), path);
+            }
         }
 
-        self.add_mod(&node.ident);
-        self.push_scope(is_test_only, false);
+        // mods with the same path behind different cfgs only count once
+        self.mod_path.push(node.ident.to_string());
+        self.mod_paths.insert(self.mod_path.join("::"));
+        self.hints.module_count = self.mod_paths.len() as u32;
+        self.hints.max_module_depth = self.hints.max_module_depth.max(self.mod_path.len() as u32);
+
+        let is_private = !matches!(node.vis, syn::Visibility::Public(_));
+        self.push_scope(cfg, false);
+        if let Some((_, items)) = &node.content {
+            let scope = self.mod_stack.back_mut().unwrap();
+            scope.uses_test_attr = uses_test_attr(items, scope.uses_test_attr);
+        }
+        self.private_depth += usize::from(is_private);
         visit::visit_item_mod(self, node);
+        self.private_depth -= usize::from(is_private);
         self.pop_scope();
+
+        self.mod_path.pop();
     }
 
     fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
-        let mut is_test_only = false;
+        // helper functions can be test-only without being tests themselves
+        let mut cfg = Cfg::parse(&node.attrs);
+        let attr_idents: Vec<syn::Ident> = node
+            .attrs
+            .iter()
+            .filter_map(|attr| match attr.parse_meta() {
+                Ok(syn::Meta::Path(path)) => path.get_ident().cloned(),
+                _ => None,
+            })
+            .collect();
+
+        let is_main = self.is_root_scope()
+            && (node.sig.ident == "main"
+                // entry point declared by a framework attribute, e.g. #[rocket::launch]
+                || node.attrs.iter().any(|attr| self.is_entry_attr(attr)));
+        let is_test = !is_main && attr_idents.iter().any(|ident| ident == "test");
+        if is_test {
+            cfg.bucket = Some(Bucket::Test);
+            // #[test] implies #[cfg(test)]
+            cfg.predicate.get_or_insert_with(|| "test".to_string());
+        }
+        if self.is_skipped_test_scope(&cfg) {
+            return;
+        }
+
+        self.add_api_markers(&node.vis, &node.attrs, node.sig.ident.to_string());
+        self.add_public_item("fn", &node.vis, &node.sig.ident);
 
-        if self.is_root_scope() && node.sig.ident == "main" {
+        if is_main {
             // main function in the top-level scope
             self.hints.has_main = true;
         } else {
-            for attr in &node.attrs {
-                if let Ok(syn::Meta::Path(path)) = attr.parse_meta() {
-                    if let Some(ident) = path.get_ident() {
-                        if ident == "test" {
-                            self.hints.has_test = true;
-                            is_test_only = true;
-                        } else if ident == "proc_macro" || ident == "proc_macro_attribute" {
-                            self.hints.has_proc_macro = true;
-                        }
-                    }
+            for ident in &attr_idents {
+                if ident == "test" {
+                    self.hints.has_test = true;
+                } else if ident == "bench" && self.config.group_test_by_mod {
+                    self.test_mods.insert(self.mod_path.join("::"));
+                } else if ident == "proc_macro" || ident == "proc_macro_attribute" {
+                    self.hints.has_proc_macro = true;
                 }
             }
+
+            // an async test needs a runtime attribute like #[tokio::test], which can also be
+            // brought in bare with `use tokio::test;`
+            let uses_test_attr = self.mod_stack.back().unwrap().uses_test_attr;
+            if is_test && node.sig.asyncness.is_some() && !uses_test_attr {
+                self.warn(
+                    "async_test_without_runtime",
+                    format!(
+                        "async test `{}` has a plain #[test] attribute, which doesn't run futures",
+                        node.sig.ident
+                    ),
+                    node.sig.ident.span(),
+                );
+            }
+
+            if is_test && self.config.emit_test_names {
+                self.add_test_fn(node);
+            }
+            if is_test && self.config.group_test_by_mod {
+                self.test_mods.insert(self.mod_path.join("::"));
+            }
         }
 
-        self.push_scope(is_test_only, false);
+        self.push_scope(cfg, false);
         visit::visit_item_fn(self, node);
         self.pop_scope();
     }
 
+    fn visit_item_macro(&mut self, node: &'ast syn::ItemMacro) {
+        // a macro invoked in item position can expand to items we can't see, including mods defined
+        // in other files; macro_rules! definitions don't expand to anything by themselves
+        if self.is_root_scope() && !node.mac.path.is_ident("macro_rules") {
+            self.hints.has_macro_generated_items = true;
+        }
+        if node.mac.path.is_ident("macro_rules")
+            && !self.parse_directives(&node.attrs).should_ignore()
+        {
+            let name = match &node.ident {
+                Some(ident) => ident.to_string(),
+                None => "macro_rules".to_string(),
+            };
+            if self.should_scan_macro(name, &node.mac.tokens, node.mac.path.span()) {
+                self.scan_macro_rules(&node.mac.tokens);
+            }
+        }
+        visit::visit_item_macro(self, node);
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+        self.add_api_markers(&node.vis, &node.attrs, node.ident.to_string());
+        self.add_public_item("trait", &node.vis, &node.ident);
+        // e.g. supertraits of a feature-gated trait are only needed with the feature
+        self.visit_with_cfg(Cfg::parse(&node.attrs), |this| {
+            visit::visit_item_trait(this, node);
+        });
+    }
+
+    fn visit_item_foreign_mod(&mut self, node: &'ast syn::ItemForeignMod) {
+        // e.g. the types in the signatures of a feature-gated extern "C" block are only needed with
+        // the feature, and so is the library it links
+        self.visit_with_cfg(Cfg::parse(&node.attrs), |this| {
+            let features = &this.mod_stack.back().unwrap().features;
+            let condition = FeatureGate::conjunction(features).unwrap_or_default();
+            for name in parse_link_names(&node.attrs) {
+                let mut native_link = NativeLink::default();
+                native_link.set_name(name);
+                native_link.set_condition(condition.clone());
+                this.native_links.push(native_link);
+            }
+
+            visit::visit_item_foreign_mod(this, node);
+        });
+    }
+
     fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
-        self.visit_type_attrs(&node.attrs);
-        visit::visit_item_struct(self, node);
+        self.add_api_markers(&node.vis, &node.attrs, node.ident.to_string());
+        self.add_public_item("struct", &node.vis, &node.ident);
+        // e.g. the field types and derives of a feature-gated struct are only needed with the
+        // feature
+        self.visit_with_cfg(Cfg::parse(&node.attrs), |this| {
+            this.visit_type_attrs(&node.attrs);
+            visit::visit_item_struct(this, node);
+        });
     }
 
     fn visit_item_enum(&mut self, node: &'ast syn::ItemEnum) {
-        self.visit_type_attrs(&node.attrs);
-        visit::visit_item_enum(self, node);
+        self.add_api_markers(&node.vis, &node.attrs, node.ident.to_string());
+        self.add_public_item("enum", &node.vis, &node.ident);
+        // variants are as public as their enum
+        for variant in &node.variants {
+            let name = format!("{}::{}", node.ident, variant.ident);
+            self.add_api_markers(&node.vis, &variant.attrs, name);
+        }
+        self.visit_with_cfg(Cfg::parse(&node.attrs), |this| {
+            this.visit_type_attrs(&node.attrs);
+            visit::visit_item_enum(this, node);
+        });
     }
 
     fn visit_item_type(&mut self, node: &'ast syn::ItemType) {
-        self.visit_type_attrs(&node.attrs);
-        visit::visit_item_type(self, node);
+        self.add_api_markers(&node.vis, &node.attrs, node.ident.to_string());
+        self.visit_with_cfg(Cfg::parse(&node.attrs), |this| {
+            this.visit_type_attrs(&node.attrs);
+            visit::visit_item_type(this, node);
+        });
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        // e.g. `#[cfg(feature = "serde")] impl serde::Serialize for Thing {}`
+        self.visit_with_cfg(Cfg::parse(&node.attrs), |this| {
+            visit::visit_item_impl(this, node);
+        });
+    }
+
+    fn visit_item_static(&mut self, node: &'ast syn::ItemStatic) {
+        self.visit_with_cfg(Cfg::parse(&node.attrs), |this| {
+            visit::visit_item_static(this, node);
+        });
+    }
+
+    fn visit_item_const(&mut self, node: &'ast syn::ItemConst) {
+        self.visit_with_cfg(Cfg::parse(&node.attrs), |this| {
+            visit::visit_item_const(this, node);
+        });
     }
 }
@@ -0,0 +1,17 @@
+#[allow(unused_imports)]
+use kept_1::SomeTrait;
+
+#[allow(dead_code, unused)]
+use kept_2::{self, Other};
+
+use not_kept::Thing;
+
+fn f() {
+    inline_path::something();
+}
+
+#[cfg(test)]
+mod tests {
+    #[allow(unused_imports)]
+    use kept_test::TestTrait;
+}
@@ -0,0 +1,7 @@
+#![allow(unused_imports)]
+
+use kept::SomeTrait;
+
+fn f() {
+    inline_path::something();
+}
@@ -0,0 +1,19 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+fn sqrt(x: f64) -> f64 {
+    libm::sqrt(x)
+}
+
+// only plain feature predicates are tracked
+#[cfg(not(any(feature = "a", feature = "b")))]
+fn other() {}
+
+struct Y;
+
+#[cfg(not(feature = "std"))]
+impl core_only::X for Y {}
@@ -0,0 +1,26 @@
+#[non_exhaustive]
+pub enum Error {
+    Io,
+    #[non_exhaustive]
+    Parse { line: usize },
+}
+
+#[non_exhaustive]
+pub(crate) struct Internal;
+
+#[non_exhaustive]
+struct Private;
+
+pub mod config {
+    #[non_exhaustive]
+    #[deprecated(note = "use Settings")]
+    pub struct Config {}
+
+    #[stable(feature = "settings", since = "1.0.0")]
+    pub fn settings() {}
+}
+
+mod private {
+    #[deprecated]
+    pub fn unreachable() {}
+}
@@ -0,0 +1,17 @@
+use std::arch::asm;
+
+fn call() {
+    unsafe {
+        asm!("call {}", sym handler_crate::handler);
+    }
+    handler_crate::init();
+}
+
+#[cfg(test)]
+mod tests {
+    fn call_in_test() {
+        unsafe {
+            asm!("call {}", sym handler_crate::test_handler);
+        }
+    }
+}
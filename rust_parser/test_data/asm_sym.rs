@@ -0,0 +1,22 @@
+use std::arch::asm;
+
+global_asm!("call {}", sym global_crate::entry);
+
+fn call() {
+    unsafe {
+        asm!("call {}", sym asm_crate::handler, out("rax") _);
+        core::arch::asm!("call {f}", f = sym named_crate::handler);
+        // local functions aren't imports
+        asm!("call {}", sym local_handler);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn call_in_test() {
+        unsafe {
+            asm!("call {}", sym test_asm_crate::handler);
+        }
+    }
+}
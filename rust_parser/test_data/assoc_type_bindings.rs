@@ -0,0 +1,24 @@
+fn where_clause<T>()
+where
+    T: Iterator<Item = serde::de::Value>,
+{
+}
+
+fn inline_bound<T: IntoIterator<Item = inline_crate::Item>>(_: T) {}
+
+fn impl_trait() -> impl Iterator<Item = return_crate::Item> {
+    std::iter::empty()
+}
+
+fn dyn_trait(_: Box<dyn Iterator<Item = dyn_crate::Item>>) {}
+
+fn nested<T>()
+where
+    T: Iterator<Item = Vec<nested_crate::Item>>,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    fn test_bound<T: Iterator<Item = test_crate::Item>>() {}
+}
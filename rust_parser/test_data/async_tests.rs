@@ -0,0 +1,14 @@
+#[test]
+async fn no_runtime() {}
+
+#[tokio::test]
+async fn with_runtime() {}
+
+#[test]
+fn sync_test() {}
+
+mod other {
+    // uses of the parent mod aren't in scope without `use super::*;`
+    #[test]
+    async fn no_runtime_in_mod() {}
+}
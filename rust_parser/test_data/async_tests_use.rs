@@ -0,0 +1,19 @@
+use tokio::test;
+
+#[test]
+async fn bare_runtime_attr() {}
+
+#[cfg(test)]
+mod tests {
+    use tokio::test;
+
+    #[test]
+    async fn runtime_in_mod() {}
+}
+
+mod inherited {
+    use super::*;
+
+    #[test]
+    async fn runtime_from_parent() {}
+}
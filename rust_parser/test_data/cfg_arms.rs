@@ -0,0 +1,27 @@
+enum Kind {
+    Json,
+    Yaml,
+    Plain,
+}
+
+fn handle(kind: Kind) {
+    match kind {
+        #[cfg(feature = "json")]
+        Kind::Json => json_crate::handle(),
+        #[cfg(not(feature = "yaml"))]
+        Kind::Yaml => fallback_crate::handle(),
+        #[cfg(test)]
+        Kind::Plain => test_crate::handle(),
+        _ => always_crate::handle(),
+    }
+
+    #[cfg(feature = "json")]
+    stmt_crate::init();
+    #[cfg(feature = "json")]
+    let _parsed = local_crate::parse();
+    #[cfg(test)]
+    {
+        block_test_crate::check();
+    }
+    after_crate::done();
+}
@@ -0,0 +1,12 @@
+#[cfg(any(unix, windows))]
+use always_crate::Thing;
+
+#[cfg(any(unix, windows))]
+mod platform {
+    use platform_crate;
+}
+
+#[cfg(unix)]
+mod unix_only {
+    use unix_crate;
+}
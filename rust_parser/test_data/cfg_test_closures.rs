@@ -0,0 +1,27 @@
+use regular_crate;
+
+fn regular() {
+    let closure = || closure_crate::call();
+    closure();
+}
+
+#[cfg(test)]
+fn test_helper() {
+    let closure = async || {
+        async_closure_crate::call().await;
+    };
+    let block = async {
+        async_block_crate::call().await;
+    };
+    let nested = |x: u32| {
+        let inner = move || nested_closure_crate::call(x);
+        inner()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    fn helper() {
+        let f = |value: test_mod_crate::Value| value;
+    }
+}
@@ -0,0 +1,12 @@
+use regular;
+
+#[cfg(test)]
+fn helper() {
+    use mockall::predicate;
+
+    mock_crate::something();
+}
+
+fn not_test() {
+    prod_crate::something();
+}
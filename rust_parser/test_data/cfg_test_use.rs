@@ -0,0 +1,12 @@
+#[cfg(test)]
+use a::{c::d, B};
+
+#[cfg(test)]
+use e;
+
+use f::G;
+
+fn g() {
+    // brought into scope by the test-only use above, so not an import
+    d::something();
+}
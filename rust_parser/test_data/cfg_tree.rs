@@ -0,0 +1,27 @@
+use regular_crate::Thing;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+struct Thing;
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Thing {
+    fn serialize(&self) -> serde_core::Result {}
+}
+
+#[cfg(all(unix, not(feature = "pure")))]
+fn native() {
+    libc::getpid();
+}
+
+#[cfg(test)]
+mod tests {
+    use test_crate::assert_thing;
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn runs() {
+        tokio::spawn(async {});
+    }
+}
@@ -0,0 +1,26 @@
+struct Buffer<const N: usize>([u8; N]);
+
+fn block_arg() -> Buffer<{ sizes::BUFFER_SIZE }> {
+    todo!()
+}
+
+fn call_arg() -> Buffer<{ limits::compute(4) }> {
+    todo!()
+}
+
+fn array_len() -> [u8; lengths::LEN] {
+    todo!()
+}
+
+fn default_arg<const N: usize = { defaults::N }>() {}
+
+fn turbofish() {
+    let _ = Buffer::<{ turbofish_crate::SIZE }>::default;
+}
+
+#[cfg(test)]
+mod tests {
+    fn test_arg() -> super::Buffer<{ test_sizes::SIZE }> {
+        todo!()
+    }
+}
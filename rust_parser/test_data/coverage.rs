@@ -0,0 +1,21 @@
+use regular;
+use both;
+
+#[cfg(coverage)]
+use coverage_crate::Thing;
+
+#[cfg(coverage)]
+use both;
+
+#[cfg(coverage)]
+mod instrumentation {
+    use coverage_mod_crate;
+
+    #[cfg(test)]
+    mod tests {
+        use test_only;
+    }
+}
+
+#[coverage(off)]
+fn not_instrumented() {}
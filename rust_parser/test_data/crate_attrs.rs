@@ -0,0 +1,4 @@
+#![recursion_limit = "256"]
+#![type_length_limit = "1048576"]
+
+fn main() {}
@@ -0,0 +1,22 @@
+use crate::macros;
+use crate::util::{self, helpers};
+
+fn f() {
+    macros::thing!();
+    util::call();
+    helpers::call();
+    external::call();
+}
+
+mod nested {
+    use crate::sibling;
+
+    fn g() {
+        sibling::call();
+    }
+}
+
+fn h() {
+    // only in scope inside nested
+    sibling::call();
+}
@@ -0,0 +1,6 @@
+use tokio::macros::support::poll_fn;
+use tokio::runtime::{self, Builder, task::{spawn, JoinHandle}};
+
+fn f() {
+    tokio::sync::mpsc::channel::<u32>(1);
+}
@@ -0,0 +1,13 @@
+#![feature(specialization)]
+
+default impl<T: bound_crate::Bound> trait_crate::Trait for T {
+    fn f(&self) -> ret_crate::Value {
+        body_crate::call()
+    }
+}
+
+impl<T> Other for Wrapper<T> {
+    default fn g(&self) {
+        default_fn_crate::call();
+    }
+}
@@ -0,0 +1,4 @@
+use serde::*;
+
+#[derive(Serialize, Deserialize)]
+struct A;
@@ -0,0 +1,2 @@
+#[derive(Clone, serde::Serialize, derive_more::Debug)]
+struct A;
@@ -0,0 +1,12 @@
+use serde::Serialize;
+use serde_with::DeserializeFromStr as FromStr;
+
+#[derive(Clone, Debug, Serialize, FromStr, thiserror::Error)]
+struct A;
+
+mod inner {
+    use schemars::JsonSchema;
+
+    #[derive(JsonSchema, PartialEq)]
+    enum B {}
+}
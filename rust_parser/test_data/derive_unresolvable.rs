@@ -0,0 +1,4 @@
+use serde::Serialize;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct A;
@@ -0,0 +1,13 @@
+fn boxed(_: Box<dyn first_crate::A + second_crate::B + Send>) {}
+
+fn referenced(_: &(dyn Send + ref_crate::C + 'static)) {}
+
+fn returned() -> Box<dyn std::error::Error + lifetime_crate::D + Sync + 'static> {
+    todo!()
+}
+
+fn impl_bounds(_: impl Clone + impl_crate::E + other_impl_crate::F) {}
+
+fn parenthesized(_: &dyn (hrtb_crate::G)) {}
+
+fn higher_ranked(_: &dyn for<'a> fn_crate::H<'a>) {}
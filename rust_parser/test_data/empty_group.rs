@@ -0,0 +1,7 @@
+use foo::{};
+use bar::baz::{};
+use {};
+
+fn f() {
+    baz::something();
+}
@@ -0,0 +1,10 @@
+#[main]
+async fn start() {}
+
+#[launch]
+fn rocket() -> _ {
+    todo!()
+}
+
+use rocket::launch;
+use tokio::main;
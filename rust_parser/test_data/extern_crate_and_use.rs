@@ -0,0 +1,9 @@
+extern crate both;
+extern crate extern_only;
+
+use both::Thing;
+use use_only::Other;
+
+fn f() {
+    path_only::call();
+}
@@ -0,0 +1,15 @@
+#[cfg(feature = "ffi")]
+#[link(name = "sodium", kind = "static")]
+extern "C" {
+    fn encrypt(ctx: *mut ffi_types::Context) -> libc::c_int;
+}
+
+#[link(name = "m")]
+extern "C" {
+    fn sqrt(x: always_crate::Double) -> f64;
+}
+
+#[cfg(test)]
+extern "C" {
+    fn mock(x: test_crate::Mock);
+}
@@ -0,0 +1,12 @@
+#[cfg(feature = "a")]
+mod m {
+    #[cfg(feature = "b")]
+    fn f() {
+        dep::x();
+    }
+}
+
+#[cfg(all(feature = "c", unix))]
+fn g() {
+    unix_dep::call();
+}
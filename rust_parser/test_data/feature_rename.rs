@@ -0,0 +1,15 @@
+#[cfg(feature = "compat")]
+use old_crate::Thing as Thing;
+#[cfg(not(feature = "compat"))]
+use new_crate::Thing;
+
+#[cfg(feature = "compat")]
+use old_crate::helpers as compat_helpers;
+#[cfg(feature = "compat")]
+use renamed_crate as legacy;
+
+fn f() -> Thing {
+    // the alias is a scope mod, not a crate
+    legacy::init();
+    compat_helpers::make()
+}
@@ -0,0 +1,38 @@
+use regular::Thing;
+
+#[cfg(feature = "async")]
+trait AsyncThing: futures::Future {}
+
+#[cfg(feature = "serde")]
+trait Serializable: serde::Serialize + Thing {}
+
+#[cfg(feature = "async")]
+mod runtime {
+    #[cfg(feature = "tokio")]
+    fn spawn() {
+        tokio::spawn(async {});
+    }
+}
+
+trait NotGated: Thing {}
+
+struct Wrapper;
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Wrapper {}
+
+#[cfg(feature = "rayon")]
+struct Pool(rayon::ThreadPool);
+
+#[cfg(feature = "log")]
+static LOGGER: Option<&dyn log::Log> = None;
+
+#[cfg(feature = "json")]
+const EMPTY: Option<serde_json::Value> = None;
+
+#[cfg(test)]
+impl Wrapper {
+    fn check() {
+        test_helper::check();
+    }
+}
@@ -0,0 +1,20 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+struct Thing;
+
+#[cfg(any(feature = "simd", all(unix, not(feature = "nightly"))))]
+fn fast() {}
+
+fn derive_enabled() -> bool {
+    cfg!(feature = "derive")
+}
+
+#[cfg(test)]
+mod tests {}
@@ -0,0 +1,5 @@
+struct Foo<T = serde_json::Value>(T);
+
+trait Handler<E = error_crate::Error> {}
+
+fn f<T: Default>() {}
@@ -0,0 +1,212 @@
+macro_rules! small {
+    () => {
+        small_crate::run()
+    };
+}
+
+macro_rules! table {
+    () => {
+        [
+        (0, table_crate::Row::new(0)),
+        (1, table_crate::Row::new(1)),
+        (2, table_crate::Row::new(2)),
+        (3, table_crate::Row::new(3)),
+        (4, table_crate::Row::new(4)),
+        (5, table_crate::Row::new(5)),
+        (6, table_crate::Row::new(6)),
+        (7, table_crate::Row::new(7)),
+        (8, table_crate::Row::new(8)),
+        (9, table_crate::Row::new(9)),
+        (10, table_crate::Row::new(10)),
+        (11, table_crate::Row::new(11)),
+        (12, table_crate::Row::new(12)),
+        (13, table_crate::Row::new(13)),
+        (14, table_crate::Row::new(14)),
+        (15, table_crate::Row::new(15)),
+        (16, table_crate::Row::new(16)),
+        (17, table_crate::Row::new(17)),
+        (18, table_crate::Row::new(18)),
+        (19, table_crate::Row::new(19)),
+        (20, table_crate::Row::new(20)),
+        (21, table_crate::Row::new(21)),
+        (22, table_crate::Row::new(22)),
+        (23, table_crate::Row::new(23)),
+        (24, table_crate::Row::new(24)),
+        (25, table_crate::Row::new(25)),
+        (26, table_crate::Row::new(26)),
+        (27, table_crate::Row::new(27)),
+        (28, table_crate::Row::new(28)),
+        (29, table_crate::Row::new(29)),
+        (30, table_crate::Row::new(30)),
+        (31, table_crate::Row::new(31)),
+        (32, table_crate::Row::new(32)),
+        (33, table_crate::Row::new(33)),
+        (34, table_crate::Row::new(34)),
+        (35, table_crate::Row::new(35)),
+        (36, table_crate::Row::new(36)),
+        (37, table_crate::Row::new(37)),
+        (38, table_crate::Row::new(38)),
+        (39, table_crate::Row::new(39)),
+        (40, table_crate::Row::new(40)),
+        (41, table_crate::Row::new(41)),
+        (42, table_crate::Row::new(42)),
+        (43, table_crate::Row::new(43)),
+        (44, table_crate::Row::new(44)),
+        (45, table_crate::Row::new(45)),
+        (46, table_crate::Row::new(46)),
+        (47, table_crate::Row::new(47)),
+        (48, table_crate::Row::new(48)),
+        (49, table_crate::Row::new(49)),
+        (50, table_crate::Row::new(50)),
+        (51, table_crate::Row::new(51)),
+        (52, table_crate::Row::new(52)),
+        (53, table_crate::Row::new(53)),
+        (54, table_crate::Row::new(54)),
+        (55, table_crate::Row::new(55)),
+        (56, table_crate::Row::new(56)),
+        (57, table_crate::Row::new(57)),
+        (58, table_crate::Row::new(58)),
+        (59, table_crate::Row::new(59)),
+        (60, table_crate::Row::new(60)),
+        (61, table_crate::Row::new(61)),
+        (62, table_crate::Row::new(62)),
+        (63, table_crate::Row::new(63)),
+        (64, table_crate::Row::new(64)),
+        (65, table_crate::Row::new(65)),
+        (66, table_crate::Row::new(66)),
+        (67, table_crate::Row::new(67)),
+        (68, table_crate::Row::new(68)),
+        (69, table_crate::Row::new(69)),
+        (70, table_crate::Row::new(70)),
+        (71, table_crate::Row::new(71)),
+        (72, table_crate::Row::new(72)),
+        (73, table_crate::Row::new(73)),
+        (74, table_crate::Row::new(74)),
+        (75, table_crate::Row::new(75)),
+        (76, table_crate::Row::new(76)),
+        (77, table_crate::Row::new(77)),
+        (78, table_crate::Row::new(78)),
+        (79, table_crate::Row::new(79)),
+        (80, table_crate::Row::new(80)),
+        (81, table_crate::Row::new(81)),
+        (82, table_crate::Row::new(82)),
+        (83, table_crate::Row::new(83)),
+        (84, table_crate::Row::new(84)),
+        (85, table_crate::Row::new(85)),
+        (86, table_crate::Row::new(86)),
+        (87, table_crate::Row::new(87)),
+        (88, table_crate::Row::new(88)),
+        (89, table_crate::Row::new(89)),
+        (90, table_crate::Row::new(90)),
+        (91, table_crate::Row::new(91)),
+        (92, table_crate::Row::new(92)),
+        (93, table_crate::Row::new(93)),
+        (94, table_crate::Row::new(94)),
+        (95, table_crate::Row::new(95)),
+        (96, table_crate::Row::new(96)),
+        (97, table_crate::Row::new(97)),
+        (98, table_crate::Row::new(98)),
+        (99, table_crate::Row::new(99)),
+        (100, table_crate::Row::new(100)),
+        (101, table_crate::Row::new(101)),
+        (102, table_crate::Row::new(102)),
+        (103, table_crate::Row::new(103)),
+        (104, table_crate::Row::new(104)),
+        (105, table_crate::Row::new(105)),
+        (106, table_crate::Row::new(106)),
+        (107, table_crate::Row::new(107)),
+        (108, table_crate::Row::new(108)),
+        (109, table_crate::Row::new(109)),
+        (110, table_crate::Row::new(110)),
+        (111, table_crate::Row::new(111)),
+        (112, table_crate::Row::new(112)),
+        (113, table_crate::Row::new(113)),
+        (114, table_crate::Row::new(114)),
+        (115, table_crate::Row::new(115)),
+        (116, table_crate::Row::new(116)),
+        (117, table_crate::Row::new(117)),
+        (118, table_crate::Row::new(118)),
+        (119, table_crate::Row::new(119)),
+        (120, table_crate::Row::new(120)),
+        (121, table_crate::Row::new(121)),
+        (122, table_crate::Row::new(122)),
+        (123, table_crate::Row::new(123)),
+        (124, table_crate::Row::new(124)),
+        (125, table_crate::Row::new(125)),
+        (126, table_crate::Row::new(126)),
+        (127, table_crate::Row::new(127)),
+        (128, table_crate::Row::new(128)),
+        (129, table_crate::Row::new(129)),
+        (130, table_crate::Row::new(130)),
+        (131, table_crate::Row::new(131)),
+        (132, table_crate::Row::new(132)),
+        (133, table_crate::Row::new(133)),
+        (134, table_crate::Row::new(134)),
+        (135, table_crate::Row::new(135)),
+        (136, table_crate::Row::new(136)),
+        (137, table_crate::Row::new(137)),
+        (138, table_crate::Row::new(138)),
+        (139, table_crate::Row::new(139)),
+        (140, table_crate::Row::new(140)),
+        (141, table_crate::Row::new(141)),
+        (142, table_crate::Row::new(142)),
+        (143, table_crate::Row::new(143)),
+        (144, table_crate::Row::new(144)),
+        (145, table_crate::Row::new(145)),
+        (146, table_crate::Row::new(146)),
+        (147, table_crate::Row::new(147)),
+        (148, table_crate::Row::new(148)),
+        (149, table_crate::Row::new(149)),
+        (150, table_crate::Row::new(150)),
+        (151, table_crate::Row::new(151)),
+        (152, table_crate::Row::new(152)),
+        (153, table_crate::Row::new(153)),
+        (154, table_crate::Row::new(154)),
+        (155, table_crate::Row::new(155)),
+        (156, table_crate::Row::new(156)),
+        (157, table_crate::Row::new(157)),
+        (158, table_crate::Row::new(158)),
+        (159, table_crate::Row::new(159)),
+        (160, table_crate::Row::new(160)),
+        (161, table_crate::Row::new(161)),
+        (162, table_crate::Row::new(162)),
+        (163, table_crate::Row::new(163)),
+        (164, table_crate::Row::new(164)),
+        (165, table_crate::Row::new(165)),
+        (166, table_crate::Row::new(166)),
+        (167, table_crate::Row::new(167)),
+        (168, table_crate::Row::new(168)),
+        (169, table_crate::Row::new(169)),
+        (170, table_crate::Row::new(170)),
+        (171, table_crate::Row::new(171)),
+        (172, table_crate::Row::new(172)),
+        (173, table_crate::Row::new(173)),
+        (174, table_crate::Row::new(174)),
+        (175, table_crate::Row::new(175)),
+        (176, table_crate::Row::new(176)),
+        (177, table_crate::Row::new(177)),
+        (178, table_crate::Row::new(178)),
+        (179, table_crate::Row::new(179)),
+        (180, table_crate::Row::new(180)),
+        (181, table_crate::Row::new(181)),
+        (182, table_crate::Row::new(182)),
+        (183, table_crate::Row::new(183)),
+        (184, table_crate::Row::new(184)),
+        (185, table_crate::Row::new(185)),
+        (186, table_crate::Row::new(186)),
+        (187, table_crate::Row::new(187)),
+        (188, table_crate::Row::new(188)),
+        (189, table_crate::Row::new(189)),
+        (190, table_crate::Row::new(190)),
+        (191, table_crate::Row::new(191)),
+        (192, table_crate::Row::new(192)),
+        (193, table_crate::Row::new(193)),
+        (194, table_crate::Row::new(194)),
+        (195, table_crate::Row::new(195)),
+        (196, table_crate::Row::new(196)),
+        (197, table_crate::Row::new(197)),
+        (198, table_crate::Row::new(198)),
+        (199, table_crate::Row::new(199)),
+        ]
+    };
+}
@@ -0,0 +1,7 @@
+fn f(x: impl a::A, y: &impl b::B) -> impl c::C {
+    todo!()
+}
+
+fn g(x: Box<dyn Fn(impl_arg::Arg)>) -> Option<impl nested_ret::Ret> {
+    todo!()
+}
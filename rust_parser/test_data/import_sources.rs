@@ -0,0 +1,18 @@
+extern crate extern_crate;
+
+use path_and_use::Thing;
+use use_only;
+
+fn f() {
+    path_only::call();
+    path_and_use::call();
+}
+
+#[cfg(test)]
+mod tests {
+    use test_use::assert_thing;
+
+    fn g() {
+        test_path_only::call();
+    }
+}
@@ -0,0 +1,15 @@
+mod inner;
+mod inline {
+    pub mod nested {}
+}
+
+pub use self::inner::*;
+pub use crate::inline::nested::*;
+pub use inner::Thing;
+pub use inline::{nested, *};
+
+pub mod child {
+    pub use super::inner::*;
+}
+
+pub use external_crate::*;
@@ -0,0 +1,8 @@
+#![deny(missing_docs, warnings)]
+#![allow(clippy::needless_return)]
+
+//! Crate docs.
+
+/// Not a crate-level lint attribute.
+#[allow(dead_code)]
+fn f() {}
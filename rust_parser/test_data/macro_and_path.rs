@@ -0,0 +1,19 @@
+macro_rules! call_foo {
+    () => {
+        foo::call()
+    };
+}
+
+fn f() {
+    foo::call();
+    regular_dep::call();
+}
+
+#[cfg(test)]
+mod tests {
+    macro_rules! check {
+        () => {
+            regular_dep::check()
+        };
+    }
+}
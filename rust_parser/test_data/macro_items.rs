@@ -0,0 +1,11 @@
+macro_rules! define_modules {
+    ($($name:ident),*) => {
+        $(mod $name;)*
+    };
+}
+
+define_modules! { a, b }
+
+fn main() {
+    println!("not an item-position macro");
+}
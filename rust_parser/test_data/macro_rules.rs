@@ -0,0 +1,37 @@
+#[macro_export]
+macro_rules! make_value {
+    ($value:expr) => {
+        $crate::values::Value::new(external_crate::convert($value))
+    };
+    ($value:expr, $module:ident) => {
+        $module::wrap(::leading_colons::wrap($value))
+    };
+    (nested_crate::looks_like_a_path) => {{
+        let value: Vec<nested_crate::Value> = Vec::new();
+        <Vec<u8> as trait_crate::Trait>::method(&value)
+    }};
+    (collect $e:expr) => {
+        $e.iter().collect::<Vec<_>>()
+    };
+    (parse $s:expr) => {
+        $s.parse::<u32>()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    macro_rules! assert_value {
+        ($value:expr) => {
+            test_crate::assert(std::convert::identity($value))
+        };
+    }
+}
+
+#[gazelle::ignore]
+macro_rules! ignored {
+    () => {
+        ignored_crate::call()
+    };
+}
+
+mod values {}
@@ -0,0 +1,23 @@
+fn matching(x: Option<u32>) {
+    match x {
+        Some(_) => {
+            use arm_crate::Ext;
+            use crate::arm_local;
+            arm_local::call();
+        }
+        None => {
+            // arm_local is only in scope in the other arm, so this is a crate
+            arm_local::call();
+        }
+    }
+}
+
+fn if_let(x: Option<u32>) {
+    if let Some(_) = x {
+        use if_let_crate::Ext;
+        use crate::if_local;
+        if_local::call();
+    } else {
+        if_local::call();
+    }
+}
@@ -0,0 +1,20 @@
+use both;
+
+#[cfg(not(miri))]
+use regular::fast_path;
+
+#[cfg(miri)]
+use pure_rust_impl::slow_path;
+
+#[cfg(miri)]
+use both;
+
+#[cfg(miri)]
+mod miri_support {
+    use miri_mod_crate;
+
+    #[cfg(test)]
+    mod tests {
+        use test_only;
+    }
+}
@@ -0,0 +1,8 @@
+extern crate Foo;
+
+use foo::Bar;
+
+#[cfg(test)]
+mod tests {
+    use foo::check;
+}
@@ -0,0 +1,13 @@
+mod a {
+    mod b {
+        mod c {}
+    }
+}
+
+mod d;
+
+#[cfg(unix)]
+mod e {}
+
+#[cfg(windows)]
+mod e {}
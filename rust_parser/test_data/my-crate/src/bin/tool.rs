@@ -0,0 +1,3 @@
+use my_crate::Thing;
+
+fn main() {}
@@ -0,0 +1,7 @@
+use my_crate::inner::Thing;
+use serde::Serialize;
+
+#[cfg(test)]
+mod tests {
+    use my_crate::Thing;
+}
@@ -0,0 +1,7 @@
+#![no_std]
+
+extern crate alloc;
+extern crate core;
+extern crate serde;
+
+use alloc::vec::Vec;
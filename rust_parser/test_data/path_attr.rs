@@ -0,0 +1,7 @@
+#[path = "other/redirected.rs"]
+mod redirected;
+
+mod plain;
+
+#[path = "inline_path"]
+mod inline {}
@@ -0,0 +1,13 @@
+fn f(x: Value, y: Bar, z: Wrapper) {
+    match x {
+        serde_json::Value::Null => {}
+        tuple_struct_crate::Thing(_) => {}
+        struct_crate::Point { x, .. } => {}
+        Value::Bool(_) => {}
+        _ => {}
+    }
+
+    let destructure_crate::Bar { a, .. } = y;
+
+    if let if_let_crate::Wrapper(inner) = z {}
+}
@@ -0,0 +1,24 @@
+use std::marker::PhantomData;
+
+struct External {
+    _marker: PhantomData<serde_json::Value>,
+}
+
+struct Nested<T> {
+    _marker: PhantomData<(T, fn() -> nested_crate::Output)>,
+}
+
+struct Local {
+    _marker: PhantomData<crate::Local>,
+}
+
+struct Qualified {
+    _marker: std::marker::PhantomData<*const qualified_crate::Raw>,
+}
+
+#[cfg(test)]
+mod tests {
+    struct TestOnly {
+        _marker: ::core::marker::PhantomData<test_crate::Value>,
+    }
+}
@@ -0,0 +1,13 @@
+mod lib_mod;
+
+#[cfg(test)]
+mod tests;
+
+fn helper() {
+    regular::call();
+}
+
+#[test]
+fn root_test() {
+    root_test_crate::check();
+}
@@ -0,0 +1,28 @@
+pub fn top() {
+    // items in function bodies aren't reachable
+    pub struct Local;
+}
+
+fn private() {}
+
+pub(crate) struct CrateOnly;
+
+pub trait Visitor {}
+
+pub mod config {
+    pub enum Format {}
+
+    pub fn load() {}
+
+    pub mod nested {
+        pub struct Deep;
+    }
+
+    mod hidden {
+        pub fn unreachable() {}
+    }
+}
+
+mod private_mod {
+    pub fn unreachable() {}
+}
@@ -0,0 +1,13 @@
+use quote::quote;
+
+fn generate() -> proc_macro2::TokenStream {
+    generated_crate::validate();
+    quote! { impl generated_crate::Trait for Thing {} }
+}
+
+#[cfg(test)]
+mod tests {
+    fn expected() -> proc_macro2::TokenStream {
+        quote::quote! { generated_crate::Trait }
+    }
+}
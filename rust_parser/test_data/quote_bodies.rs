@@ -0,0 +1,20 @@
+use proc_macro::TokenStream;
+use quote::quote;
+
+#[proc_macro_derive(Generated)]
+pub fn derive(input: TokenStream) -> TokenStream {
+    let module = syn::parse_macro_input!(input as syn::DeriveInput).ident;
+    quote! {
+        impl generated_crate::Trait for #module::Thing {
+            fn run() -> spanned_crate::Output {}
+        }
+    }
+    .into()
+}
+
+macro_rules! generate {
+    ($name:ident) => {
+        rules_crate::check();
+        quote::quote! { impl rules_quote_crate::Trait for $name {} }
+    };
+}
@@ -0,0 +1,8 @@
+pub use public_crate::Thing1;
+pub(crate) use crate_vis::Thing2;
+pub(super) use super_vis::Thing3;
+pub(in crate::api) use in_path_vis::Thing4;
+pub(self) use self_vis::Thing5;
+use private_crate::Thing6;
+
+mod api {}
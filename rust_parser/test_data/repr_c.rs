@@ -0,0 +1,11 @@
+#[repr(C)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[repr(u8)]
+enum Tag {
+    A,
+    B,
+}
@@ -0,0 +1,23 @@
+trait Plain
+where
+    Self: Sized + external::Marker,
+{
+}
+
+trait Generic<T>
+where
+    Self: other_crate::Trait<T> + 'static,
+    T: bound_crate::Bound,
+{
+    fn method(&self)
+    where
+        Self: method_crate::Send;
+}
+
+trait HigherRanked
+where
+    for<'a> &'a Self: hrtb_crate::Visit<'a>,
+{
+}
+
+impl<T> Plain for T where Self: impl_crate::Marker {}
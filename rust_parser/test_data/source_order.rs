@@ -0,0 +1,13 @@
+use zeta::Z;
+use alpha::A;
+
+fn f() {
+    middle::call();
+    zeta::call();
+}
+
+#[cfg(test)]
+mod tests {
+    use test_z;
+    use test_a;
+}
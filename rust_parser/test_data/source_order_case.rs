@@ -0,0 +1,7 @@
+use alpha::Thing;
+
+fn f() {
+    middle::call();
+}
+
+extern crate Zeta;
@@ -0,0 +1,47 @@
+#[test]
+fn root_test() {}
+
+fn not_a_test() {}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn plain() {}
+
+    #[test]
+    #[should_panic]
+    fn panics() {}
+
+    #[test]
+    #[should_panic(expected = "boom")]
+    fn panics_with_message() {}
+
+    #[test]
+    #[ignore]
+    fn ignored() {}
+
+    #[test]
+    #[ignore = "slow"]
+    fn ignored_with_reason() {}
+
+    fn helper() {}
+
+    mod nested {
+        #[test]
+        async fn async_test() {}
+
+        #[test]
+        #[should_panic]
+        #[ignore]
+        fn everything() {}
+    }
+}
+
+mod benches {
+    #[bench]
+    fn bench(b: &mut test::Bencher) {}
+}
+
+mod no_tests {
+    fn helper() {}
+}
@@ -0,0 +1,12 @@
+use libtest_mimic::{Arguments, Trial};
+
+fn main() {
+    let args = Arguments::from_args();
+    let check = Trial::test("check", || Ok(my_crate::check()));
+    libtest_mimic::run(&args, Vec::from([check])).exit();
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+}
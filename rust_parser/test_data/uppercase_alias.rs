@@ -0,0 +1,7 @@
+extern crate Foo;
+
+use regular;
+
+fn f() -> String {
+    String::new()
+}
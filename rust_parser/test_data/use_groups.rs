@@ -0,0 +1,12 @@
+use ::{serde, tokio::runtime};
+use {anyhow::Result, log as logging};
+use outer::{self as renamed_outer, inner_mod as renamed_inner};
+use nested::{deep::{self as deep_alias}};
+
+fn f() {
+    // aliases are scope mods, not crates
+    renamed_outer::a();
+    renamed_inner::b();
+    deep_alias::c();
+    logging::info();
+}
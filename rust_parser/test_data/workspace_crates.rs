@@ -0,0 +1,8 @@
+use regular_crate::Thing;
+use serde::Serialize;
+
+#[cfg(test)]
+mod tests {
+    use test_crate;
+    use test_helper::assert_thing;
+}
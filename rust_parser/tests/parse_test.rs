@@ -52,6 +52,179 @@ lazy_static::lazy_static! {
             ],
             expected_extern_mods: vec![],
         },
+        TestCase {
+            filename: "empty_group.rs",
+            expected_imports: vec!["foo", "bar", "baz"],
+            expected_test_imports: vec![],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "patterns.rs",
+            expected_imports: vec![
+                "serde_json",
+                "tuple_struct_crate",
+                "struct_crate",
+                "destructure_crate",
+                "if_let_crate",
+            ],
+            expected_test_imports: vec![],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "cfg_platform.rs",
+            expected_imports: vec!["always_crate", "platform_crate", "unix_crate"],
+            expected_test_imports: vec![],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "cfg_test_fn.rs",
+            expected_imports: vec!["regular", "prod_crate"],
+            expected_test_imports: vec!["mockall", "mock_crate"],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "cfg_test_use.rs",
+            expected_imports: vec!["f"],
+            expected_test_imports: vec!["a", "e"],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "impl_trait.rs",
+            expected_imports: vec!["a", "b", "c", "impl_arg", "nested_ret"],
+            expected_test_imports: vec![],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "reexports.rs",
+            expected_imports: vec![
+                "public_crate",
+                "crate_vis",
+                "super_vis",
+                "in_path_vis",
+                "self_vis",
+                "private_crate",
+            ],
+            expected_test_imports: vec![],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "generic_defaults.rs",
+            expected_imports: vec!["serde_json", "error_crate"],
+            expected_test_imports: vec![],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "assoc_type_bindings.rs",
+            expected_imports: vec![
+                "serde",
+                "inline_crate",
+                "return_crate",
+                "dyn_crate",
+                "nested_crate",
+                "std",
+            ],
+            expected_test_imports: vec!["test_crate"],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "cfg_test_closures.rs",
+            expected_imports: vec!["regular_crate", "closure_crate"],
+            expected_test_imports: vec![
+                "async_closure_crate",
+                "async_block_crate",
+                "nested_closure_crate",
+                "test_mod_crate",
+            ],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "phantom_data.rs",
+            expected_imports: vec!["std", "serde_json", "nested_crate", "qualified_crate"],
+            expected_test_imports: vec!["core", "test_crate"],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "macro_rules.rs",
+            expected_imports: vec![
+                "external_crate",
+                "leading_colons",
+                "nested_crate",
+                "trait_crate",
+                "gazelle",
+            ],
+            expected_test_imports: vec!["test_crate", "std"],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "crate_use_mod.rs",
+            expected_imports: vec!["external", "sibling"],
+            expected_test_imports: vec![],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "const_generics.rs",
+            expected_imports: vec![
+                "sizes",
+                "limits",
+                "lengths",
+                "defaults",
+                "turbofish_crate",
+            ],
+            expected_test_imports: vec!["test_sizes"],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "match_arm_use.rs",
+            expected_imports: vec![
+                "arm_crate",
+                "arm_local",
+                "if_let_crate",
+                "if_local",
+            ],
+            expected_test_imports: vec![],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "dyn_bounds.rs",
+            expected_imports: vec![
+                "first_crate",
+                "second_crate",
+                "ref_crate",
+                "std",
+                "lifetime_crate",
+                "impl_crate",
+                "other_impl_crate",
+                "hrtb_crate",
+                "fn_crate",
+            ],
+            expected_test_imports: vec![],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "asm_sym.rs",
+            expected_imports: vec!["std", "global_crate", "asm_crate", "core", "named_crate"],
+            expected_test_imports: vec!["test_asm_crate"],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "use_groups.rs",
+            expected_imports: vec!["serde", "tokio", "anyhow", "log", "outer", "nested"],
+            expected_test_imports: vec![],
+            expected_extern_mods: vec![],
+        },
+        TestCase {
+            filename: "self_bounds.rs",
+            expected_imports: vec![
+                "external",
+                "other_crate",
+                "bound_crate",
+                "method_crate",
+                "hrtb_crate",
+                "impl_crate",
+            ],
+            expected_test_imports: vec![],
+            expected_extern_mods: vec![],
+        },
     ];
 }
 
@@ -80,9 +253,15 @@ fn assert_eq_vecs(actual: &[String], expected: &[String]) {
     }
 }
 
-#[test]
-fn parse_test() -> Result<(), Box<dyn Error>> {
-    let dir = if cfg!(feature = "bazel") {
+fn parse_test_file(filename: &str) -> Result<parser::RustImports, Box<dyn Error>> {
+    parse_test_file_with_config(filename, &parser::ParseConfig::default())
+}
+
+fn parse_test_file_with_config(
+    filename: &str,
+    config: &parser::ParseConfig,
+) -> Result<parser::RustImports, Box<dyn Error>> {
+    let mut file = if cfg!(feature = "bazel") {
         let mut d = runfiles::find_runfiles_dir()?;
         d.push("gazelle_rust/rust_parser/test_data");
         d
@@ -91,12 +270,15 @@ fn parse_test() -> Result<(), Box<dyn Error>> {
         d.push("test_data");
         d
     };
+    file.push(filename);
 
-    for test_case in &*TEST_CASES {
-        let mut file = dir.clone();
-        file.push(test_case.filename);
+    parser::parse_imports(file, config)
+}
 
-        let rust_imports = parser::parse_imports(file)?;
+#[test]
+fn parse_test() -> Result<(), Box<dyn Error>> {
+    for test_case in &*TEST_CASES {
+        let rust_imports = parse_test_file(test_case.filename)?;
         assert_eq_vecs(
             &rust_imports.imports,
             &test_case
@@ -125,3 +307,1082 @@ fn parse_test() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn repr_c_test() -> Result<(), Box<dyn Error>> {
+    assert!(parse_test_file("repr_c.rs")?.hints.has_repr_c);
+    assert!(!parse_test_file("simple.rs")?.hints.has_repr_c);
+
+    Ok(())
+}
+
+#[test]
+fn macro_generated_items_test() -> Result<(), Box<dyn Error>> {
+    assert!(
+        parse_test_file("macro_items.rs")?
+            .hints
+            .has_macro_generated_items
+    );
+    assert!(
+        !parse_test_file("simple.rs")?
+            .hints
+            .has_macro_generated_items
+    );
+
+    Ok(())
+}
+
+#[test]
+fn crate_limits_test() -> Result<(), Box<dyn Error>> {
+    let hints = parse_test_file("crate_attrs.rs")?.hints;
+    assert_eq!(hints.recursion_limit, 256);
+    assert_eq!(hints.type_length_limit, 1048576);
+
+    let hints = parse_test_file("simple.rs")?.hints;
+    assert_eq!(hints.recursion_limit, 0);
+    assert_eq!(hints.type_length_limit, 0);
+
+    Ok(())
+}
+
+#[test]
+fn module_structure_test() -> Result<(), Box<dyn Error>> {
+    let hints = parse_test_file("modules.rs")?.hints;
+    assert_eq!(hints.module_count, 5);
+    assert_eq!(hints.max_module_depth, 3);
+
+    let hints = parse_test_file("repr_c.rs")?.hints;
+    assert_eq!(hints.module_count, 0);
+    assert_eq!(hints.max_module_depth, 0);
+
+    Ok(())
+}
+
+#[test]
+fn analyze_test() -> Result<(), Box<dyn Error>> {
+    let ast = syn::parse_file("use foo::Bar; fn f() { baz::something(); }")?;
+    // the config doesn't have to outlive the analysis
+    let analysis = parser::analyze(&ast, &parser::ParseConfig::default());
+
+    let mut imports: Vec<_> = analysis
+        .imports
+        .iter()
+        .map(|ident| ident.as_ident().to_string())
+        .collect();
+    imports.sort();
+    assert_eq!(imports, vec!["baz", "foo"]);
+
+    Ok(())
+}
+
+#[test]
+fn keep_imports_test() -> Result<(), Box<dyn Error>> {
+    let mut keep_imports = parse_test_file("allow_unused.rs")?.keep_imports;
+    keep_imports.sort();
+    assert_eq!(keep_imports, vec!["kept_1", "kept_2", "kept_test"]);
+
+    let keep_imports = parse_test_file("allow_unused_file.rs")?.keep_imports;
+    assert_eq!(keep_imports, vec!["kept"]);
+
+    Ok(())
+}
+
+#[test]
+fn extern_mod_paths_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("path_attr.rs")?;
+
+    let mut extern_mods = rust_imports.extern_mods;
+    extern_mods.sort();
+    assert_eq!(extern_mods, vec!["plain", "redirected"]);
+
+    assert_eq!(rust_imports.extern_mod_paths.len(), 1);
+    assert_eq!(
+        rust_imports.extern_mod_paths["redirected"],
+        "other/redirected.rs"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn crate_lint_levels_test() -> Result<(), Box<dyn Error>> {
+    let hints = parse_test_file("lint_levels.rs")?.hints;
+    let lint_levels: Vec<_> = hints
+        .crate_lint_levels
+        .iter()
+        .map(|lint_level| (lint_level.level.as_str(), lint_level.lint.as_str()))
+        .collect();
+    assert_eq!(
+        lint_levels,
+        vec![
+            ("deny", "missing_docs"),
+            ("deny", "warnings"),
+            ("allow", "clippy::needless_return"),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn reexports_test() -> Result<(), Box<dyn Error>> {
+    assert_eq!(
+        parse_test_file("reexports.rs")?.reexports,
+        vec!["public_crate"]
+    );
+    // re-exports of the crate's own mods aren't crates
+    let rust_imports = parse_test_file("internal_reexports.rs")?;
+    assert_eq!(rust_imports.reexports, vec!["external_crate"]);
+    assert_eq!(rust_imports.imports, vec!["external_crate"]);
+
+    Ok(())
+}
+
+#[test]
+fn entry_attrs_test() -> Result<(), Box<dyn Error>> {
+    assert!(!parse_test_file("entry_attr.rs")?.hints.has_main);
+
+    let config = parser::ParseConfig {
+        entry_attrs: vec!["rocket::launch".to_string()],
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("entry_attr.rs", &config)?;
+    assert!(rust_imports.hints.has_main);
+    assert_eq!(rust_imports.imports, vec!["rocket"]);
+
+    Ok(())
+}
+
+#[test]
+fn unresolvable_derive_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("derive_resolvable.rs")?;
+    assert!(!rust_imports.hints.has_unresolvable_derive);
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &[
+            "serde".to_string(),
+            "serde_with".to_string(),
+            "thiserror".to_string(),
+            "schemars".to_string(),
+        ],
+    );
+
+    assert!(rust_imports.diagnostics.is_empty());
+
+    let rust_imports = parse_test_file("derive_unresolvable.rs")?;
+    assert!(rust_imports.hints.has_unresolvable_derive);
+    assert_eq!(rust_imports.diagnostics.len(), 1);
+    assert_eq!(
+        rust_imports.diagnostics[0].get_code(),
+        "unresolvable_derive"
+    );
+    // the derive could come from the glob
+    assert!(
+        !parse_test_file("derive_glob.rs")?
+            .hints
+            .has_unresolvable_derive
+    );
+
+    Ok(())
+}
+
+#[test]
+fn no_std_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("no_std.rs")?;
+    assert!(rust_imports.hints.is_no_std);
+    assert_eq!(rust_imports.std_externs, vec!["alloc", "core"]);
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &["alloc".to_string(), "core".to_string(), "serde".to_string()],
+    );
+
+    let rust_imports = parse_test_file("simple.rs")?;
+    assert!(!rust_imports.hints.is_no_std);
+    assert!(rust_imports.std_externs.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn max_macro_tokens_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("huge_macro.rs")?;
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &["small_crate".to_string(), "table_crate".to_string()],
+    );
+    assert!(rust_imports.macros_skipped.is_empty());
+
+    let config = parser::ParseConfig {
+        max_macro_tokens: Some(100),
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("huge_macro.rs", &config)?;
+    assert_eq_vecs(&rust_imports.imports, &["small_crate".to_string()]);
+    assert_eq!(rust_imports.macros_skipped, vec!["table"]);
+    let codes: Vec<&str> = rust_imports
+        .diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.get_code())
+        .collect();
+    assert_eq!(codes, vec!["macro_skipped"]);
+
+    Ok(())
+}
+
+#[test]
+fn public_api_markers_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("api_markers.rs")?;
+    let markers: Vec<(&str, &str)> = rust_imports
+        .hints
+        .public_api_markers
+        .iter()
+        .map(|marker| (marker.get_marker(), marker.get_item()))
+        .collect();
+    // only public items outside private mods
+    assert_eq!(
+        markers,
+        vec![
+            ("non_exhaustive", "Error"),
+            ("non_exhaustive", "Error::Parse"),
+            ("non_exhaustive", "config::Config"),
+            ("deprecated", "config::Config"),
+            ("stable", "config::settings"),
+        ]
+    );
+
+    assert!(parse_test_file("simple.rs")?
+        .hints
+        .public_api_markers
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn quote_bodies_test() -> Result<(), Box<dyn Error>> {
+    // the quoted code is compiled in the caller's crate
+    let rust_imports = parse_test_file("quote_bodies.rs")?;
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &[
+            "proc_macro".to_string(),
+            "quote".to_string(),
+            "syn".to_string(),
+            "rules_crate".to_string(),
+        ],
+    );
+
+    let config = parser::ParseConfig {
+        scan_quote_bodies: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("quote_bodies.rs", &config)?;
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &[
+            "proc_macro".to_string(),
+            "quote".to_string(),
+            "syn".to_string(),
+            "rules_crate".to_string(),
+            "generated_crate".to_string(),
+            "spanned_crate".to_string(),
+            "rules_quote_crate".to_string(),
+        ],
+    );
+
+    Ok(())
+}
+
+#[test]
+fn quote_and_path_test() -> Result<(), Box<dyn Error>> {
+    // a crate named inside and outside quote! is only imported once, and not again for tests
+    let config = parser::ParseConfig {
+        scan_quote_bodies: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("quote_and_path.rs", &config)?;
+    assert_eq!(
+        rust_imports.imports,
+        vec!["generated_crate", "proc_macro2", "quote"]
+    );
+    assert!(rust_imports.test_imports.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn public_api_test() -> Result<(), Box<dyn Error>> {
+    assert!(parse_test_file("public_api.rs")?.public_api.is_empty());
+
+    let config = parser::ParseConfig {
+        emit_public_api: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("public_api.rs", &config)?;
+    assert_eq!(
+        rust_imports.public_api,
+        vec![
+            "enum config::Format",
+            "fn config::load",
+            "fn top",
+            "struct config::nested::Deep",
+            "trait Visitor",
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn async_test_without_runtime_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("async_tests.rs")?;
+    assert_eq!(rust_imports.diagnostics.len(), 2);
+    assert_eq!(
+        rust_imports.diagnostics[0].get_code(),
+        "async_test_without_runtime"
+    );
+    assert!(rust_imports.diagnostics[0]
+        .get_message()
+        .contains("no_runtime"));
+    assert!(rust_imports.diagnostics[1]
+        .get_message()
+        .contains("no_runtime_in_mod"));
+    // located at the function name
+    let locations: Vec<(u32, u32)> = rust_imports
+        .diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.get_line(), diagnostic.get_column()))
+        .collect();
+    assert_eq!(locations, vec![(2, 10), (13, 14)]);
+
+    // #[test] refers to tokio::test here, including in mods that use it themselves or through
+    // `use super::*;`
+    assert!(parse_test_file("async_tests_use.rs")?
+        .diagnostics
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn deep_use_path_test() -> Result<(), Box<dyn Error>> {
+    // only the leading segment is a crate, however deep the path goes
+    let rust_imports = parse_test_file("deep_use_path.rs")?;
+    assert_eq!(rust_imports.imports, vec!["tokio"]);
+    assert!(rust_imports.test_imports.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn prod_only_test() -> Result<(), Box<dyn Error>> {
+    let config = parser::ParseConfig {
+        prod_only: true,
+        ..Default::default()
+    };
+
+    let rust_imports = parse_test_file_with_config("test_only.rs", &config)?;
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &[
+            "a".to_string(),
+            "x".to_string(),
+            "m".to_string(),
+            "n".to_string(),
+        ],
+    );
+    assert!(rust_imports.test_imports.is_empty());
+
+    // #[cfg(test)] uses aren't scopes of their own
+    let rust_imports = parse_test_file_with_config("cfg_test_use.rs", &config)?;
+    assert_eq_vecs(&rust_imports.imports, &["f".to_string()]);
+    assert!(rust_imports.test_imports.is_empty());
+
+    // test mods in other files aren't followed, and test functions don't show up in the hints
+    let config = parser::ParseConfig {
+        prod_only: true,
+        emit_test_names: true,
+        group_test_by_mod: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("prod_only.rs", &config)?;
+    assert_eq!(rust_imports.imports, ["regular"]);
+    assert_eq!(rust_imports.extern_mods, ["lib_mod"]);
+    assert_eq!(rust_imports.hints.module_count, 1);
+    assert!(!rust_imports.hints.has_test);
+    assert!(rust_imports.test_fns.is_empty());
+    assert!(rust_imports.test_mods.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn derive_mixed_test() -> Result<(), Box<dyn Error>> {
+    // bare derives are builtins or come from a use, so only the qualified ones name crates
+    let rust_imports = parse_test_file("derive_mixed.rs")?;
+    assert_eq!(rust_imports.imports, vec!["derive_more", "serde"]);
+    assert!(!rust_imports.hints.has_unresolvable_derive);
+
+    Ok(())
+}
+
+#[test]
+fn default_impl_test() -> Result<(), Box<dyn Error>> {
+    // syn parses specialization syntax, so the bounds, trait and bodies are all visited
+    let rust_imports = parse_test_file("default_impl.rs")?;
+    assert_eq!(
+        rust_imports.imports,
+        vec![
+            "body_crate",
+            "bound_crate",
+            "default_fn_crate",
+            "ret_crate",
+            "trait_crate"
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn asm_and_path_test() -> Result<(), Box<dyn Error>> {
+    // a crate named in a sym operand and in the AST is only imported once
+    let rust_imports = parse_test_file("asm_and_path.rs")?;
+    assert_eq!(rust_imports.imports, vec!["handler_crate", "std"]);
+    assert!(rust_imports.test_imports.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn macro_and_path_test() -> Result<(), Box<dyn Error>> {
+    // a crate named in a macro body and in the AST is only imported once
+    let rust_imports = parse_test_file("macro_and_path.rs")?;
+    assert_eq!(rust_imports.imports, vec!["foo", "regular_dep"]);
+    assert!(rust_imports.test_imports.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn crate_name_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("my-crate/src/lib.rs")?;
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &["my_crate".to_string(), "serde".to_string()],
+    );
+
+    let config = parser::ParseConfig {
+        crate_name: Some("my_crate".to_string()),
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("my-crate/src/lib.rs", &config)?;
+    assert_eq_vecs(&rust_imports.imports, &["serde".to_string()]);
+    assert!(rust_imports.test_imports.is_empty());
+
+    let config = parser::ParseConfig {
+        crate_name_from_path: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("my-crate/src/lib.rs", &config)?;
+    assert_eq_vecs(&rust_imports.imports, &["serde".to_string()]);
+    assert!(rust_imports.test_imports.is_empty());
+
+    // binaries depend on the library of the same name
+    let rust_imports = parse_test_file_with_config("my-crate/src/bin/tool.rs", &config)?;
+    assert_eq_vecs(&rust_imports.imports, &["my_crate".to_string()]);
+
+    // no src directory to infer from
+    let rust_imports = parse_test_file_with_config("simple.rs", &config)?;
+    assert!(!rust_imports.imports.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn entry_attrs_use_test() -> Result<(), Box<dyn Error>> {
+    assert!(!parse_test_file("entry_attr_use.rs")?.hints.has_main);
+
+    let config = parser::ParseConfig {
+        entry_attrs: vec!["tokio::main".to_string()],
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("entry_attr_use.rs", &config)?;
+    assert!(rust_imports.hints.has_main);
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &["rocket".to_string(), "tokio".to_string()],
+    );
+
+    Ok(())
+}
+
+#[test]
+fn coverage_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("coverage.rs")?;
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &["regular".to_string(), "both".to_string()],
+    );
+    assert_eq_vecs(&rust_imports.test_imports, &["test_only".to_string()]);
+    assert_eq_vecs(
+        &rust_imports.coverage_imports,
+        &[
+            "coverage_crate".to_string(),
+            "coverage_mod_crate".to_string(),
+        ],
+    );
+    assert!(rust_imports.hints.has_coverage_off);
+
+    assert!(!parse_test_file("simple.rs")?.hints.has_coverage_off);
+
+    Ok(())
+}
+
+#[test]
+fn content_hash_test() -> Result<(), Box<dyn Error>> {
+    assert_eq!(parse_test_file("simple.rs")?.content_hash, None);
+
+    let config = parser::ParseConfig {
+        emit_hash: true,
+        ..Default::default()
+    };
+    let simple_hash = parse_test_file_with_config("simple.rs", &config)?.content_hash;
+    let test_only_hash = parse_test_file_with_config("test_only.rs", &config)?.content_hash;
+
+    assert_eq!(simple_hash.as_ref().map(String::len), Some(16));
+    assert_eq!(
+        simple_hash,
+        parse_test_file_with_config("simple.rs", &config)?.content_hash
+    );
+    assert_ne!(simple_hash, test_only_hash);
+
+    Ok(())
+}
+
+#[test]
+fn workspace_crates_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("test_only.rs")?;
+    assert!(rust_imports.workspace_imports.is_empty());
+    assert!(rust_imports.external_imports.is_empty());
+
+    let config = parser::ParseConfig {
+        workspace_crates: vec!["regular_crate".to_string(), "test_crate".to_string()],
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("workspace_crates.rs", &config)?;
+    assert_eq_vecs(
+        &rust_imports.workspace_imports,
+        &["regular_crate".to_string(), "test_crate".to_string()],
+    );
+    assert_eq_vecs(
+        &rust_imports.external_imports,
+        &["serde".to_string(), "test_helper".to_string()],
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_fns_test() -> Result<(), Box<dyn Error>> {
+    assert!(parse_test_file("test_fns.rs")?.test_fns.is_empty());
+
+    let config = parser::ParseConfig {
+        emit_test_names: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("test_fns.rs", &config)?;
+    let test_fns: Vec<(&str, Vec<&str>)> = rust_imports
+        .test_fns
+        .iter()
+        .map(|test_fn| {
+            (
+                test_fn.get_name(),
+                test_fn.get_modifiers().iter().map(String::as_str).collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        test_fns,
+        vec![
+            ("root_test", vec![]),
+            ("tests::plain", vec![]),
+            ("tests::panics", vec!["should_panic"]),
+            ("tests::panics_with_message", vec!["should_panic"]),
+            ("tests::ignored", vec!["ignore"]),
+            ("tests::ignored_with_reason", vec!["ignore"]),
+            ("tests::nested::async_test", vec!["async"]),
+            ("tests::nested::everything", vec!["should_panic", "ignore"]),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn import_sources_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("import_sources.rs")?;
+    let import_sources: Vec<(&str, bool)> = rust_imports
+        .import_sources
+        .iter()
+        .map(|import_source| (import_source.get_name(), import_source.get_has_use()))
+        .collect();
+    assert_eq!(
+        import_sources,
+        vec![
+            ("extern_crate", true),
+            ("path_and_use", true),
+            ("path_only", false),
+            ("test_path_only", false),
+            ("test_use", true),
+            ("use_only", true),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn import_provenance_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("extern_crate_and_use.rs")?;
+    let import_sources: Vec<(&str, bool, bool)> = rust_imports
+        .import_sources
+        .iter()
+        .map(|import_source| {
+            (
+                import_source.get_name(),
+                import_source.get_via_extern_crate(),
+                import_source.get_via_use(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        import_sources,
+        vec![
+            ("both", true, true),
+            ("extern_only", true, false),
+            ("path_only", false, false),
+            ("use_only", false, true),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn normalize_case_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("uppercase_alias.rs")?;
+    assert_eq_vecs(&rust_imports.imports, &["regular".to_string()]);
+
+    let config = parser::ParseConfig {
+        normalize_case: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("uppercase_alias.rs", &config)?;
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &[
+            "regular".to_string(),
+            "foo".to_string(),
+            "string".to_string(),
+        ],
+    );
+
+    Ok(())
+}
+
+#[test]
+fn normalize_case_dedup_test() -> Result<(), Box<dyn Error>> {
+    // `Foo` normalizes to the same crate as `foo`
+    let config = parser::ParseConfig {
+        normalize_case: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("mixed_case.rs", &config)?;
+    assert_eq!(rust_imports.imports, vec!["foo"]);
+    assert!(rust_imports.test_imports.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_mods_test() -> Result<(), Box<dyn Error>> {
+    assert!(parse_test_file("test_fns.rs")?.test_mods.is_empty());
+
+    let config = parser::ParseConfig {
+        group_test_by_mod: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("test_fns.rs", &config)?;
+    assert_eq!(
+        rust_imports.test_mods,
+        vec![
+            "".to_string(),
+            "benches".to_string(),
+            "tests".to_string(),
+            "tests::nested".to_string(),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn miri_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("miri.rs")?;
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &["regular".to_string(), "both".to_string()],
+    );
+    assert_eq_vecs(&rust_imports.test_imports, &["test_only".to_string()]);
+    assert_eq_vecs(
+        &rust_imports.miri_imports,
+        &["pure_rust_impl".to_string(), "miri_mod_crate".to_string()],
+    );
+    assert!(rust_imports.coverage_imports.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn source_order_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("source_order.rs")?;
+    assert_eq!(rust_imports.imports, vec!["alpha", "middle", "zeta"]);
+    assert_eq!(rust_imports.test_imports, vec!["test_a", "test_z"]);
+
+    let config = parser::ParseConfig {
+        source_order: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("source_order.rs", &config)?;
+    assert_eq!(rust_imports.imports, vec!["zeta", "alpha", "middle"]);
+    assert_eq!(rust_imports.test_imports, vec!["test_z", "test_a"]);
+
+    // crates that are only named with an uppercase alias are ordered by where that appears
+    let config = parser::ParseConfig {
+        source_order: true,
+        normalize_case: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("source_order_case.rs", &config)?;
+    assert_eq!(rust_imports.imports, vec!["alpha", "middle", "zeta"]);
+
+    Ok(())
+}
+
+#[test]
+fn feature_imports_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("feature_trait.rs")?;
+    // features can't be modeled yet, so everything is still a regular import too
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &[
+            "futures".to_string(),
+            "serde".to_string(),
+            "tokio".to_string(),
+            "regular".to_string(),
+            "rayon".to_string(),
+            "log".to_string(),
+            "serde_json".to_string(),
+        ],
+    );
+    assert_eq_vecs(&rust_imports.test_imports, &["test_helper".to_string()]);
+
+    let mut features: Vec<&String> = rust_imports.feature_imports.keys().collect();
+    features.sort();
+    assert_eq!(
+        features,
+        vec!["async", "json", "log", "rayon", "serde", "tokio"]
+    );
+    assert_eq_vecs(
+        &rust_imports.feature_imports["async"],
+        &["futures".to_string(), "tokio".to_string()],
+    );
+    assert_eq_vecs(
+        &rust_imports.feature_imports["serde"],
+        &["serde".to_string()],
+    );
+    // the features of the enclosing scopes apply too
+    assert_eq_vecs(
+        &rust_imports.feature_imports["tokio"],
+        &["tokio".to_string()],
+    );
+    // impls, structs, statics and consts apply their cfgs too
+    assert_eq_vecs(
+        &rust_imports.feature_imports["rayon"],
+        &["rayon".to_string()],
+    );
+    assert_eq_vecs(&rust_imports.feature_imports["log"], &["log".to_string()]);
+    assert_eq_vecs(
+        &rust_imports.feature_imports["json"],
+        &["serde_json".to_string()],
+    );
+
+    assert!(parse_test_file("simple.rs")?.feature_imports.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn feature_nested_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("feature_nested.rs")?;
+    assert_eq!(rust_imports.feature_imports["a"], vec!["dep"]);
+    assert_eq!(rust_imports.feature_imports["b"], vec!["dep"]);
+    assert_eq!(rust_imports.feature_imports["c"], vec!["unix_dep"]);
+
+    // nested gates all have to hold, and other predicates within all(...) are left out
+    assert_eq!(
+        rust_imports.import_conditions["dep"],
+        "all(feature = \"a\", feature = \"b\")"
+    );
+    assert_eq!(
+        rust_imports.import_conditions["unix_dep"],
+        "feature = \"c\""
+    );
+
+    Ok(())
+}
+
+#[test]
+fn treat_bin_as_test_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("test_harness.rs")?;
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &["libtest_mimic".to_string(), "my_crate".to_string()],
+    );
+    assert_eq_vecs(
+        &rust_imports.test_imports,
+        &["pretty_assertions".to_string()],
+    );
+
+    let config = parser::ParseConfig {
+        treat_bin_as_test: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("test_harness.rs", &config)?;
+    assert!(rust_imports.hints.has_main);
+    assert!(rust_imports.imports.is_empty());
+    assert_eq_vecs(
+        &rust_imports.test_imports,
+        &[
+            "libtest_mimic".to_string(),
+            "my_crate".to_string(),
+            "pretty_assertions".to_string(),
+        ],
+    );
+
+    // files without a main function are unaffected
+    let rust_imports = parse_test_file_with_config("test_only.rs", &config)?;
+    assert!(!rust_imports.imports.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn anti_feature_imports_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("anti_feature.rs")?;
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &[
+            "alloc".to_string(),
+            "std".to_string(),
+            "libm".to_string(),
+            "core_only".to_string(),
+        ],
+    );
+
+    assert_eq!(rust_imports.anti_feature_imports.len(), 1);
+    assert_eq_vecs(
+        &rust_imports.anti_feature_imports["std"],
+        &[
+            "alloc".to_string(),
+            "libm".to_string(),
+            "core_only".to_string(),
+        ],
+    );
+    assert_eq!(rust_imports.feature_imports.len(), 1);
+    assert_eq_vecs(&rust_imports.feature_imports["std"], &["std".to_string()]);
+
+    Ok(())
+}
+
+#[test]
+fn feature_rename_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("feature_rename.rs")?;
+    // the aliases are scope mods, not crates
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &[
+            "old_crate".to_string(),
+            "new_crate".to_string(),
+            "renamed_crate".to_string(),
+        ],
+    );
+
+    assert_eq!(rust_imports.feature_imports.len(), 1);
+    assert_eq_vecs(
+        &rust_imports.feature_imports["compat"],
+        &["old_crate".to_string(), "renamed_crate".to_string()],
+    );
+    assert_eq!(rust_imports.anti_feature_imports.len(), 1);
+    assert_eq_vecs(
+        &rust_imports.anti_feature_imports["compat"],
+        &["new_crate".to_string()],
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cfg_arms_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("cfg_arms.rs")?;
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &[
+            "json_crate".to_string(),
+            "fallback_crate".to_string(),
+            "always_crate".to_string(),
+            "stmt_crate".to_string(),
+            "local_crate".to_string(),
+            "after_crate".to_string(),
+        ],
+    );
+    assert_eq_vecs(
+        &rust_imports.test_imports,
+        &["test_crate".to_string(), "block_test_crate".to_string()],
+    );
+
+    assert_eq!(rust_imports.feature_imports.len(), 1);
+    assert_eq_vecs(
+        &rust_imports.feature_imports["json"],
+        &[
+            "json_crate".to_string(),
+            "stmt_crate".to_string(),
+            "local_crate".to_string(),
+        ],
+    );
+    assert_eq!(rust_imports.anti_feature_imports.len(), 1);
+    assert_eq_vecs(
+        &rust_imports.anti_feature_imports["yaml"],
+        &["fallback_crate".to_string()],
+    );
+
+    Ok(())
+}
+
+#[test]
+fn feature_extern_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file("feature_extern.rs")?;
+    assert_eq_vecs(
+        &rust_imports.imports,
+        &[
+            "ffi_types".to_string(),
+            "libc".to_string(),
+            "always_crate".to_string(),
+        ],
+    );
+    assert_eq_vecs(&rust_imports.test_imports, &["test_crate".to_string()]);
+    assert_eq!(rust_imports.feature_imports.len(), 1);
+    assert_eq_vecs(
+        &rust_imports.feature_imports["ffi"],
+        &["ffi_types".to_string(), "libc".to_string()],
+    );
+
+    let native_links: Vec<(&str, &str)> = rust_imports
+        .native_links
+        .iter()
+        .map(|native_link| (native_link.get_name(), native_link.get_condition()))
+        .collect();
+    assert_eq!(
+        native_links,
+        vec![("sodium", "feature = \"ffi\""), ("m", "")]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cfg_tree_test() -> Result<(), Box<dyn Error>> {
+    let rust_imports = parse_test_file_with_config(
+        "cfg_tree.rs",
+        &parser::ParseConfig {
+            emit_cfg_tree: true,
+            ..Default::default()
+        },
+    )?;
+    let root = rust_imports.cfg_tree.expect("cfg tree was requested");
+    assert_eq!(root.predicate, None);
+    assert_eq!(root.bucket, "regular");
+    assert_eq!(root.imports, vec!["regular_crate"]);
+
+    let children: Vec<(Option<&str>, &str, &str, &[String])> = root
+        .children
+        .iter()
+        .map(|scope| {
+            (
+                scope.predicate.as_deref(),
+                scope.bucket,
+                scope.condition.as_str(),
+                &scope.imports[..],
+            )
+        })
+        .collect();
+    assert_eq!(
+        children,
+        vec![
+            (
+                Some("feature = \"serde\""),
+                "regular",
+                "feature = \"serde\"",
+                &["serde".to_string()][..]
+            ),
+            (
+                Some("feature = \"serde\""),
+                "regular",
+                "feature = \"serde\"",
+                &["serde".to_string(), "serde_core".to_string()][..]
+            ),
+            (
+                Some("all(unix, not(feature = \"pure\"))"),
+                "regular",
+                "not(feature = \"pure\")",
+                &["libc".to_string()][..]
+            ),
+            (Some("test"), "test", "", &["test_crate".to_string()][..]),
+        ]
+    );
+
+    let tests = &root.children[3];
+    assert_eq!(tests.children.len(), 1);
+    assert_eq!(
+        tests.children[0].predicate.as_deref(),
+        Some("feature = \"async\"")
+    );
+    assert_eq!(tests.children[0].bucket, "test");
+    assert_eq!(tests.children[0].condition, "feature = \"async\"");
+    assert_eq!(tests.children[0].imports, vec!["tokio"]);
+
+    // only collected on request
+    assert!(parse_test_file("cfg_tree.rs")?.cfg_tree.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn referenced_features_test() -> Result<(), Box<dyn Error>> {
+    assert!(parse_test_file("features.rs")?
+        .referenced_features
+        .is_empty());
+
+    let config = parser::ParseConfig {
+        list_features: true,
+        ..Default::default()
+    };
+    let rust_imports = parse_test_file_with_config("features.rs", &config)?;
+    assert_eq!(
+        rust_imports.referenced_features,
+        vec!["alloc", "derive", "nightly", "serde", "simd", "std"]
+    );
+
+    Ok(())
+}